@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use heapless::String;
+use kiibohd_hid_io::{
+    h0001, h0016, h0031, h0034, CommandError, Commands, HidIoCommandId, HidIoPacketBuffer,
+};
+
+use crate::transport::{Error, Transport, PACKET_LEN};
+
+/// Max data payload length, matches the firmware
+pub const H: usize = 256;
+/// Max number of supported command ids
+pub const ID: usize = 10;
+
+/// How long to wait for the keyboard to answer
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Ack/Nak received for a command that was sent to the keyboard
+#[derive(Debug)]
+enum Reply {
+    Ack(HidIoCommandId),
+    Nak(HidIoCommandId, std::string::String),
+}
+
+/// Host side of the HID-IO protocol
+pub struct HostInterface<T: Transport> {
+    transport: T,
+    rx_packetbuf: HidIoPacketBuffer<H>,
+    replies: VecDeque<Reply>,
+    info: Option<h0001::Ack<{ H - 1 }>>,
+    output: VecDeque<std::string::String>,
+}
+
+impl<T: Transport> HostInterface<T> {
+    pub fn new(transport: T) -> Self {
+        HostInterface {
+            transport,
+            rx_packetbuf: HidIoPacketBuffer::new(),
+            replies: VecDeque::new(),
+            info: None,
+            output: VecDeque::new(),
+        }
+    }
+
+    /// receive and handle a single packet
+    fn poll(&mut self, timeout: Duration) -> Result<(), Error> {
+        let Some(packet) = self.transport.recv(timeout)? else {
+            return Ok(());
+        };
+        self.rx_packetbuf
+            .decode_packet(&packet)
+            .map_err(CommandError::PacketDecodeError)?;
+        if self.rx_packetbuf.done {
+            let buf = self.rx_packetbuf.clone();
+            self.rx_packetbuf.clear();
+            self.rx_message_handling(buf)?;
+        }
+        Ok(())
+    }
+
+    /// wait for the keyboard to Ack or Nak the given command
+    fn wait_reply(&mut self, id: HidIoCommandId) -> Result<(), Error> {
+        let start = Instant::now();
+        while start.elapsed() < TIMEOUT {
+            if let Some(pos) = self.replies.iter().position(|r| match r {
+                Reply::Ack(rid) | Reply::Nak(rid, _) => *rid == id,
+            }) {
+                return match self.replies.remove(pos).unwrap() {
+                    Reply::Ack(_) => Ok(()),
+                    Reply::Nak(_, why) => Err(Error::Rejected(why)),
+                };
+            }
+            self.poll(TIMEOUT - start.elapsed())?;
+        }
+        Err(Error::Timeout)
+    }
+
+    /// request a single info property from the keyboard
+    pub fn info(&mut self, property: h0001::Property) -> Result<h0001::Ack<{ H - 1 }>, Error> {
+        self.h0001_info(h0001::Cmd { property })?;
+        self.wait_reply(HidIoCommandId::GetInfo)?;
+        self.info.take().ok_or(Error::Timeout)
+    }
+
+    /// send a terminal command to the keyboard
+    pub fn terminal(&mut self, command: &str) -> Result<(), Error> {
+        self.output.clear();
+        let mut cmd = String::new();
+        cmd.push_str(command).map_err(|_| Error::TooLong)?;
+        self.h0031_terminalcmd(h0031::Cmd { command: cmd }, false)?;
+        self.wait_reply(HidIoCommandId::TerminalCmd)
+    }
+
+    /// wait for the next line of terminal output
+    pub fn read_line(&mut self) -> Result<std::string::String, Error> {
+        let start = Instant::now();
+        while start.elapsed() < TIMEOUT {
+            if let Some(line) = self.output.pop_front() {
+                return Ok(line);
+            }
+            self.poll(TIMEOUT - start.elapsed())?;
+        }
+        Err(Error::Timeout)
+    }
+
+    /// the transport, e.g. to look at the state of a mock keyboard
+    #[cfg(test)]
+    pub fn transport(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// ask the keyboard to jump to its bootloader
+    pub fn flash_mode(&mut self) -> Result<(), Error> {
+        self.h0016_flashmode(h0016::Cmd {})?;
+        self.wait_reply(HidIoCommandId::FlashMode)
+    }
+}
+
+impl<T: Transport> Commands<H, { H - 1 }, { H - 2 }, { H - 4 }, ID> for HostInterface<T> {
+    fn default_packet_chunk(&self) -> u32 {
+        PACKET_LEN as u32
+    }
+
+    fn tx_packetbuffer_send(&mut self, buf: &mut HidIoPacketBuffer<H>) -> Result<(), CommandError> {
+        let mut data = vec![0u8; buf.serialized_len() as usize];
+        buf.serialize_buffer(&mut data)
+            .map_err(CommandError::SerializationFailed)?;
+        for packet in data.chunks(PACKET_LEN) {
+            self.transport
+                .send(packet)
+                .map_err(|_| CommandError::TxBufferSendFailed)?;
+        }
+        Ok(())
+    }
+
+    fn h0001_info_ack(&mut self, data: h0001::Ack<{ H - 1 }>) -> Result<(), CommandError> {
+        self.info = Some(data);
+        self.replies.push_back(Reply::Ack(HidIoCommandId::GetInfo));
+        Ok(())
+    }
+
+    fn h0001_info_nak(&mut self, data: h0001::Nak) -> Result<(), CommandError> {
+        self.replies.push_back(Reply::Nak(
+            HidIoCommandId::GetInfo,
+            format!("{:?} not available", data.property),
+        ));
+        Ok(())
+    }
+
+    fn h0016_flashmode_ack(&mut self, _data: h0016::Ack) -> Result<(), CommandError> {
+        self.replies
+            .push_back(Reply::Ack(HidIoCommandId::FlashMode));
+        Ok(())
+    }
+
+    fn h0016_flashmode_nak(&mut self, data: h0016::Nak) -> Result<(), CommandError> {
+        self.replies.push_back(Reply::Nak(
+            HidIoCommandId::FlashMode,
+            format!("{:?}", data.error),
+        ));
+        Ok(())
+    }
+
+    fn h0031_terminalcmd_ack(&mut self, _data: h0031::Ack) -> Result<(), CommandError> {
+        self.replies
+            .push_back(Reply::Ack(HidIoCommandId::TerminalCmd));
+        Ok(())
+    }
+
+    fn h0031_terminalcmd_nak(&mut self, _data: h0031::Nak) -> Result<(), CommandError> {
+        self.replies.push_back(Reply::Nak(
+            HidIoCommandId::TerminalCmd,
+            "unknown command".into(),
+        ));
+        Ok(())
+    }
+
+    fn h0034_terminalout_cmd(&mut self, data: h0034::Cmd<H>) -> Result<h0034::Ack, h0034::Nak> {
+        self.output.push_back(data.output.as_str().into());
+        Ok(h0034::Ack {})
+    }
+
+    fn h0034_terminalout_nacmd(&mut self, data: h0034::Cmd<H>) -> Result<(), CommandError> {
+        self.output.push_back(data.output.as_str().into());
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+mod host;
+mod mock;
+mod transport;
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::process::ExitCode;
+
+use kiibohd_hid_io::h0001::Property;
+
+use host::HostInterface;
+use mock::MockTransport;
+use transport::{Error, HidTransport, Transport};
+
+const USAGE: &str = "\
+usage: ergoone-cli [--mock] <command>
+
+commands:
+  info                      show device and firmware information
+  dump-keymap               print every keymap entry as `row col entry`
+  set-key <row> <col> <key> replace a key, e.g. `set-key 0 1 df,Ltr_Azzz`
+  stats                     print how often each key was pressed, laid out like the matrix
+  stats clear               zero the press counts
+  chatter                   list switches that chattered(closed again right after
+                            opening), worn ones are marked
+  chatter clear             forget the chatter, e.g. after replacing a switch
+  wpm                       print the current typing speed in words per minute
+  rgb <r> <g> <b>           set the LED color
+  hsv <h> <s> <v>           set the LED color as hue, saturation and value(0-255)
+  effect <name>             set the LED effect, one of solid, breathing, rainbow,
+                            gradient, wave, reactive or heatmap
+  speed <0-255|wpm>         set the LED effect speed, `wpm` follows the typing speed
+  brightness <0-255>        set the LED brightness
+  idle <seconds>            fade the LEDs out after this long without typing, 0 never
+  wakeup <row> <col> <on|off>
+                            allow or stop a key waking the computer from sleep
+  frame <rrggbb>...         show a hex color on each LED instead of the effect,
+                            e.g. `frame ff0000 00ff00 ...` with one per LED
+  matrix-test               stop typing and list every switch as it is pressed,
+                            finishes once every matrix position was seen
+  matrix-test off           go back to typing, e.g. after quitting a test early
+  hand                      show whether a split half is the left or right one
+  hand <left|right>         save the hand of the half plugged in, takes effect on
+                            the next boot
+  bootloader                reboot the keyboard into the USB bootloader
+
+options:
+  --mock                    talk to an in-process mock keyboard instead of hardware";
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mock = match args.iter().position(|a| a == "--mock") {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    };
+    if args.is_empty() || args[0] == "--help" || args[0] == "-h" {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let res = if mock {
+        run(&mut HostInterface::new(MockTransport::new()), &args)
+    } else {
+        HidTransport::open().and_then(|t| run(&mut HostInterface::new(t), &args))
+    };
+    match res {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(Error::Usage) => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// run a single subcommand against a keyboard
+fn run<T: Transport>(intf: &mut HostInterface<T>, args: &[String]) -> Result<(), Error> {
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    match args.as_slice() {
+        ["info"] => {
+            for (name, property) in [
+                ("Device", Property::DeviceName),
+                ("Firmware", Property::FirmwareName),
+            ] {
+                println!("{:<10}{}", name, intf.info(property)?.string);
+            }
+            let mut version = Vec::new();
+            for property in [
+                Property::MajorVersion,
+                Property::MinorVersion,
+                Property::PatchVersion,
+            ] {
+                version.push(intf.info(property)?.number.to_string());
+            }
+            println!("{:<10}{}", "HID-IO", version.join("."));
+        }
+        ["dump-keymap"] => {
+            intf.terminal("keymap")?;
+            loop {
+                let line = intf.read_line()?;
+                if line == "end" {
+                    break;
+                }
+                println!("{}", line);
+            }
+        }
+        ["stats"] => {
+            intf.terminal("stats")?;
+            let mut grid: Vec<Vec<u32>> = Vec::new();
+            loop {
+                let line = intf.read_line()?;
+                if line == "end" {
+                    break;
+                }
+                // each line is `row col count`
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let parsed = match fields[..] {
+                    [row, col, count] => row
+                        .parse()
+                        .ok()
+                        .zip(col.parse().ok())
+                        .zip(count.parse().ok()),
+                    _ => None,
+                };
+                let Some(((row, col), count)) = parsed else {
+                    return Err(Error::Rejected(line));
+                };
+                if grid.len() <= row {
+                    grid.resize(row + 1, Vec::new());
+                }
+                if grid[row].len() <= col {
+                    grid[row].resize(col + 1, 0);
+                }
+                grid[row][col] = count;
+            }
+            let width = grid.iter().flatten().max().unwrap_or(&0).to_string().len();
+            for row in &grid {
+                let counts: Vec<String> = row.iter().map(|c| format!("{:>width$}", c)).collect();
+                println!("{}", counts.join(" "));
+            }
+            println!("total {}", grid.iter().flatten().sum::<u32>());
+        }
+        ["chatter"] => {
+            intf.terminal("chatter")?;
+            let mut any = false;
+            loop {
+                let line = intf.read_line()?;
+                if line == "end" {
+                    break;
+                }
+                // each line is `row col count` with ` worn` on flagged switches
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let [row, col, count, ref rest @ ..] = fields[..] else {
+                    return Err(Error::Rejected(line));
+                };
+                let worn = if rest == ["worn"] { "  worn" } else { "" };
+                println!("row {} col {}: {} times{}", row, col, count, worn);
+                any = true;
+            }
+            if !any {
+                println!("no chatter seen");
+            }
+        }
+        ["chatter", "clear"] => {
+            intf.terminal("chatter clear")?;
+            reply(intf)?;
+        }
+        ["wpm"] => {
+            intf.terminal("wpm")?;
+            println!("{} wpm", intf.read_line()?);
+        }
+        ["stats", "clear"] => {
+            intf.terminal("stats clear")?;
+            reply(intf)?;
+        }
+        ["set-key", row, col, key] => {
+            intf.terminal(&format!("setkey {} {} {}", row, col, key))?;
+            reply(intf)?;
+        }
+        [cmd @ ("rgb" | "hsv"), a, b, c] => {
+            intf.terminal(&format!("{} {} {} {}", cmd, a, b, c))?;
+            reply(intf)?;
+        }
+        [cmd @ ("effect" | "speed" | "brightness" | "idle"), value] => {
+            intf.terminal(&format!("{} {}", cmd, value))?;
+            reply(intf)?;
+        }
+        ["wakeup", row, col, enabled] => {
+            intf.terminal(&format!("wakeup {} {} {}", row, col, enabled))?;
+            reply(intf)?;
+        }
+        ["frame", colors @ ..] if !colors.is_empty() => {
+            intf.terminal(&format!("frame {}", colors.concat()))?;
+            reply(intf)?;
+        }
+        ["matrix-test"] => {
+            // every position in the keymap has a switch, spacers included
+            intf.terminal("keymap")?;
+            let mut total = 0;
+            while intf.read_line()? != "end" {
+                total += 1;
+            }
+            intf.terminal("matrixtest on")?;
+            reply(intf)?;
+            println!("press every switch, {} to go", total);
+            let mut seen = HashSet::new();
+            while seen.len() < total {
+                let line = match intf.read_line() {
+                    Ok(line) => line,
+                    // nobody pressed anything for a while
+                    Err(Error::Timeout) => continue,
+                    Err(err) => return Err(err),
+                };
+                // each line is `down row col` or `up row col`
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let ["down", row, col] = fields[..] else {
+                    continue;
+                };
+                seen.insert((row.to_string(), col.to_string()));
+                println!("row {} col {}  {}/{}", row, col, seen.len(), total);
+            }
+            stop_matrix_test(intf)?;
+            println!("every switch works");
+        }
+        ["matrix-test", "off"] => stop_matrix_test(intf)?,
+        ["hand"] => {
+            intf.terminal("hand")?;
+            // `hand source`, e.g. `left usb`
+            let line = intf.read_line()?;
+            let Some((hand, from)) = line.split_once(' ') else {
+                return Err(Error::Rejected(line));
+            };
+            println!("{} half, from {}", hand, from);
+        }
+        ["hand", hand @ ("left" | "right")] => {
+            intf.terminal(&format!("hand {}", hand))?;
+            reply(intf)?;
+            println!("saved, replug the half to use it");
+        }
+        ["bootloader", rest @ ..] => {
+            if !rest.contains(&"--yes") && !confirm("Reboot the keyboard into the bootloader?") {
+                return Ok(());
+            }
+            // the firmware ignores flash mode unless it was armed first
+            intf.terminal("bootloader arm")?;
+            reply(intf)?;
+            intf.flash_mode()?;
+            println!("keyboard is rebooting into the bootloader");
+        }
+        _ => return Err(Error::Usage),
+    }
+    Ok(())
+}
+
+/// ask the user a yes/no question on the terminal
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush().ok();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).ok();
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+/// leave the matrix test, switch events sent before it stopped are skipped
+fn stop_matrix_test<T: Transport>(intf: &mut HostInterface<T>) -> Result<(), Error> {
+    intf.terminal("matrixtest off")?;
+    loop {
+        match intf.read_line()?.as_str() {
+            "ok" => return Ok(()),
+            line if line.starts_with("down ") || line.starts_with("up ") => continue,
+            err => {
+                return Err(Error::Rejected(
+                    err.trim_start_matches("err").trim().to_string(),
+                ))
+            }
+        }
+    }
+}
+
+/// wait for an `ok` or `err ...` reply from the keyboard
+fn reply<T: Transport>(intf: &mut HostInterface<T>) -> Result<(), Error> {
+    match intf.read_line()?.as_str() {
+        "ok" => Ok(()),
+        err => Err(Error::Rejected(
+            err.trim_start_matches("err").trim().to_string(),
+        )),
+    }
+}
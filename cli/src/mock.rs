@@ -0,0 +1,376 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use heapless::String;
+use kiibohd_hid_io::{h0016, h0031, h0034, CommandInterface, Commands, KiibohdCommandInterface};
+
+use crate::host::{H, ID};
+use crate::transport::{Error, Transport, PACKET_LEN};
+
+const ROWS: usize = 5;
+const COLS: usize = 16;
+/// LEDs on the strip, see src/rgb/mod.rs
+const LEDS: usize = 8;
+/// Chatter count that flags a switch as worn, see src/scanner/chatter.rs
+const CHATTER_FLAG: u16 = 5;
+/// LED effects the firmware knows, see src/rgb/effects.rs
+const EFFECTS: [&str; 7] = [
+    "solid",
+    "breathing",
+    "rainbow",
+    "gradient",
+    "wave",
+    "reactive",
+    "heatmap",
+];
+
+/// In-process stand-in for the keyboard firmware
+/// Answers the same HID-IO commands so the tool can be exercised without hardware
+pub struct MockKeyboard {
+    keymap: Vec<std::string::String>,
+    counts: Vec<u32>,
+    chatter: Vec<u16>,
+    rgb: (u8, u8, u8),
+    effect: std::string::String,
+    speed: u8,
+    speed_wpm: bool,
+    brightness: u8,
+    idle_timeout: u16,
+    no_wakeup: Vec<(usize, usize)>,
+    frame: Option<Vec<(u8, u8, u8)>>,
+    /// hand saved in flash, the mock is a left half going by USB until one is saved
+    hand: Option<std::string::String>,
+    bootloader_armed: bool,
+    output: VecDeque<std::string::String>,
+}
+
+impl MockKeyboard {
+    pub fn new() -> Self {
+        MockKeyboard {
+            keymap: vec!["df,________".into(); ROWS * COLS],
+            counts: vec![0; ROWS * COLS],
+            chatter: vec![0; ROWS * COLS],
+            rgb: (0, 0, 0),
+            effect: "solid".into(),
+            speed: 128,
+            speed_wpm: false,
+            brightness: 255,
+            idle_timeout: 300,
+            no_wakeup: Vec::new(),
+            frame: None,
+            hand: None,
+            bootloader_armed: false,
+            output: VecDeque::new(),
+        }
+    }
+
+    fn terminal(&mut self, command: &str) -> bool {
+        let args: Vec<&str> = command.split_whitespace().collect();
+        match args.as_slice() {
+            ["keymap"] => {
+                for (i, entry) in self.keymap.iter().enumerate() {
+                    self.output
+                        .push_back(format!("{} {} {}", i / COLS, i % COLS, entry));
+                }
+                self.output.push_back("end".into());
+            }
+            ["stats"] => {
+                for (i, count) in self.counts.iter().enumerate() {
+                    self.output
+                        .push_back(format!("{} {} {}", i / COLS, i % COLS, count));
+                }
+                self.output.push_back("end".into());
+            }
+            ["chatter"] => {
+                for (i, count) in self.chatter.iter().enumerate() {
+                    if *count > 0 {
+                        let worn = if *count >= CHATTER_FLAG { " worn" } else { "" };
+                        self.output.push_back(format!(
+                            "{} {} {}{}",
+                            i / COLS,
+                            i % COLS,
+                            count,
+                            worn
+                        ));
+                    }
+                }
+                self.output.push_back("end".into());
+            }
+            ["chatter", "clear"] => {
+                self.chatter.fill(0);
+                self.output.push_back("ok".into());
+            }
+            // nobody types on the mock
+            ["wpm"] => self.output.push_back("0".into()),
+            ["stats", "clear"] => {
+                self.counts.fill(0);
+                self.output.push_back("ok".into());
+            }
+            ["setkey", row, col, entry] => match (row.parse::<usize>(), col.parse::<usize>()) {
+                (Ok(row), Ok(col)) if row < ROWS && col < COLS => {
+                    self.keymap[row * COLS + col] = entry.to_string();
+                    self.output.push_back("ok".into());
+                }
+                _ => self.output.push_back("err position out of range".into()),
+            },
+            ["rgb", r, g, b] => match (r.parse(), g.parse(), b.parse()) {
+                (Ok(r), Ok(g), Ok(b)) => {
+                    self.rgb = (r, g, b);
+                    self.output.push_back("ok".into());
+                }
+                _ => return false,
+            },
+            ["hsv", h, s, v] => match (h.parse::<u8>(), s.parse::<u8>(), v.parse::<u8>()) {
+                (Ok(_), Ok(_), Ok(_)) => self.output.push_back("ok".into()),
+                _ => return false,
+            },
+            ["effect", name] => {
+                if !EFFECTS.contains(name) {
+                    return false;
+                }
+                self.effect = name.to_string();
+                self.output.push_back("ok".into());
+            }
+            ["speed", "wpm"] => {
+                self.speed_wpm = true;
+                self.output.push_back("ok".into());
+            }
+            ["speed", speed] => match speed.parse() {
+                Ok(speed) => {
+                    self.speed = speed;
+                    self.speed_wpm = false;
+                    self.output.push_back("ok".into());
+                }
+                _ => return false,
+            },
+            ["brightness", brightness] => match brightness.parse() {
+                Ok(brightness) => {
+                    self.brightness = brightness;
+                    self.output.push_back("ok".into());
+                }
+                _ => return false,
+            },
+            ["idle", seconds] => match seconds.parse() {
+                Ok(seconds) => {
+                    self.idle_timeout = seconds;
+                    self.output.push_back("ok".into());
+                }
+                _ => return false,
+            },
+            ["wakeup", row, col, enabled @ ("on" | "off")] => {
+                match (row.parse::<usize>(), col.parse::<usize>()) {
+                    (Ok(row), Ok(col)) if row < ROWS && col < COLS => {
+                        self.no_wakeup.retain(|&p| p != (row, col));
+                        if *enabled == "off" {
+                            self.no_wakeup.push((row, col));
+                        }
+                        self.output.push_back("ok".into());
+                    }
+                    _ => self.output.push_back("err position out of range".into()),
+                }
+            }
+            ["frame", hex] => {
+                let byte = |i: usize| hex.get(i * 2..i * 2 + 2).map(|b| u8::from_str_radix(b, 16));
+                let frame = (0..LEDS)
+                    .map(|i| match (byte(i * 3), byte(i * 3 + 1), byte(i * 3 + 2)) {
+                        (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => Some((r, g, b)),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>();
+                match frame {
+                    Some(frame) if hex.len() == LEDS * 6 => {
+                        self.frame = Some(frame);
+                        self.output.push_back("ok".into());
+                    }
+                    _ => return false,
+                }
+            }
+            ["matrixtest", "on"] => {
+                self.output.push_back("ok".into());
+                // a tech pressing every switch in turn
+                for i in 0..ROWS * COLS {
+                    for state in ["down", "up"] {
+                        self.output
+                            .push_back(format!("{} {} {}", state, i / COLS, i % COLS));
+                    }
+                }
+            }
+            ["matrixtest", "off"] => self.output.push_back("ok".into()),
+            ["hand"] => {
+                let line = match &self.hand {
+                    Some(hand) => format!("{} flash", hand),
+                    None => "left usb".into(),
+                };
+                self.output.push_back(line);
+            }
+            ["hand", hand @ ("left" | "right")] => {
+                self.hand = Some(hand.to_string());
+                self.output.push_back("ok".into());
+            }
+            ["bootloader", "arm"] => {
+                self.bootloader_armed = true;
+                self.output.push_back("ok".into());
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl KiibohdCommandInterface<H> for MockKeyboard {
+    fn h0001_device_name(&self) -> Option<&str> {
+        Some("ErgoOne (mock)")
+    }
+
+    fn h0001_firmware_name(&self) -> Option<&str> {
+        Some("ErgoOne")
+    }
+
+    fn h0016_flashmode_cmd(&mut self, _data: h0016::Cmd) -> Result<h0016::Ack, h0016::Nak> {
+        if std::mem::take(&mut self.bootloader_armed) {
+            Ok(h0016::Ack { scancode: 0 })
+        } else {
+            Err(h0016::Nak {
+                error: h0016::Error::Disabled,
+            })
+        }
+    }
+
+    fn h0031_terminalinput(&mut self, data: h0031::Cmd<H>) -> bool {
+        self.terminal(data.command.as_str())
+    }
+}
+
+/// Transport that feeds packets straight into a MockKeyboard
+pub struct MockTransport {
+    intf: CommandInterface<MockKeyboard, 8, 8, PACKET_LEN, H, 277, ID>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        use kiibohd_hid_io::HidIoCommandId::*;
+        MockTransport {
+            intf: CommandInterface::new(
+                &[
+                    SupportedIds,
+                    GetInfo,
+                    TestPacket,
+                    FlashMode,
+                    TerminalCmd,
+                    TerminalOut,
+                ],
+                MockKeyboard::new(),
+            )
+            .unwrap(),
+        }
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&mut self, packet: &[u8]) -> Result<(), Error> {
+        self.intf
+            .rx_bytebuf
+            .enqueue(heapless::Vec::from_slice(packet).unwrap())
+            .map_err(|_| Error::Timeout)?;
+        self.intf.process_rx(0)?;
+        Ok(())
+    }
+
+    fn recv(&mut self, _timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        // Like the firmware main loop, only queue output once there is room for it
+        if self.intf.tx_bytebuf.is_empty() {
+            if let Some(line) = self.intf.mut_interface().output.pop_front() {
+                self.intf.h0034_terminalout(
+                    h0034::Cmd {
+                        output: String::from(line.as_str()),
+                    },
+                    true,
+                )?;
+            }
+        }
+        Ok(self.intf.tx_bytebuf.dequeue().map(|p| p.to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::HostInterface;
+    use crate::run;
+
+    fn mock() -> HostInterface<MockTransport> {
+        HostInterface::new(MockTransport::new())
+    }
+
+    fn cli(intf: &mut HostInterface<MockTransport>, args: &[&str]) -> Result<(), Error> {
+        let args: Vec<std::string::String> = args.iter().map(|a| a.to_string()).collect();
+        run(intf, &args)
+    }
+
+    fn keyboard(intf: &mut HostInterface<MockTransport>) -> &mut MockKeyboard {
+        intf.transport().intf.mut_interface()
+    }
+
+    /// every line of a dump up to "end"
+    fn dump(intf: &mut HostInterface<MockTransport>, command: &str) -> Vec<std::string::String> {
+        intf.terminal(command).unwrap();
+        let mut lines = Vec::new();
+        loop {
+            let line = intf.read_line().unwrap();
+            if line == "end" {
+                return lines;
+            }
+            lines.push(line);
+        }
+    }
+
+    #[test]
+    fn dump_keymap() {
+        let mut intf = mock();
+        cli(&mut intf, &["dump-keymap"]).unwrap();
+        let lines = dump(&mut intf, "keymap");
+        assert_eq!(lines.len(), ROWS * COLS);
+        assert_eq!(lines[0], "0 0 df,________");
+        assert_eq!(lines[ROWS * COLS - 1], "4 15 df,________");
+    }
+
+    #[test]
+    fn set_key() {
+        let mut intf = mock();
+        cli(&mut intf, &["set-key", "0", "1", "mt,Fun_Escz,Mod_LCtl"]).unwrap();
+        assert_eq!(dump(&mut intf, "keymap")[1], "0 1 mt,Fun_Escz,Mod_LCtl");
+        let err = cli(&mut intf, &["set-key", "5", "0", "df,Ltr_Azzz"]);
+        assert!(matches!(err, Err(Error::Rejected(why)) if why == "position out of range"));
+    }
+
+    #[test]
+    fn rgb() {
+        let mut intf = mock();
+        cli(&mut intf, &["rgb", "1", "2", "3"]).unwrap();
+        assert_eq!(keyboard(&mut intf).rgb, (1, 2, 3));
+        // not a u8, the keyboard doesn't take the command
+        assert!(cli(&mut intf, &["rgb", "1", "2", "300"]).is_err());
+        assert_eq!(keyboard(&mut intf).rgb, (1, 2, 3));
+    }
+
+    #[test]
+    fn too_long_command() {
+        let mut intf = mock();
+        // a frame for far more LEDs than a terminal command holds
+        let colors = vec!["ff0000"; 100];
+        let mut args = vec!["frame"];
+        args.extend(colors);
+        assert!(matches!(cli(&mut intf, &args), Err(Error::TooLong)));
+    }
+
+    #[test]
+    fn bootloader_arm() {
+        let mut intf = mock();
+        // flash mode is refused unless it was armed first
+        assert!(matches!(intf.flash_mode(), Err(Error::Rejected(_))));
+        cli(&mut intf, &["bootloader", "--yes"]).unwrap();
+        // arming only lets one flash mode through
+        assert!(!keyboard(&mut intf).bootloader_armed);
+        assert!(matches!(intf.flash_mode(), Err(Error::Rejected(_))));
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt;
+use std::time::Duration;
+
+use hidapi::{HidApi, HidDevice, HidError};
+use kiibohd_hid_io::CommandError;
+
+/// VID/PID the firmware enumerates with
+pub const VID: u16 = 0x16c0;
+pub const PID: u16 = 0x27dd;
+/// Vendor usage page of the HID-IO interface
+pub const HIDIO_USAGE_PAGE: u16 = 0xff1c;
+/// Size of a single HID-IO report
+pub const PACKET_LEN: usize = 64;
+
+#[derive(Debug)]
+pub enum Error {
+    /// the command line didn't match any subcommand
+    Usage,
+    /// no ErgoOne HID-IO interface is connected
+    NotFound,
+    /// the device didn't answer in time
+    Timeout,
+    /// the device refused the command
+    Rejected(String),
+    /// the command doesn't fit in a HID-IO terminal command
+    TooLong,
+    Hid(HidError),
+    Protocol(CommandError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Usage => write!(f, "invalid command line"),
+            Error::NotFound => write!(f, "no ErgoOne found ({:04x}:{:04x})", VID, PID),
+            Error::Timeout => write!(f, "timed out waiting for the keyboard"),
+            Error::Rejected(why) => write!(f, "keyboard rejected the command: {}", why),
+            Error::TooLong => write!(f, "command too long for the keyboard"),
+            Error::Hid(err) => write!(f, "hid error: {}", err),
+            Error::Protocol(err) => write!(f, "hid-io error: {:?}", err),
+        }
+    }
+}
+
+impl From<HidError> for Error {
+    fn from(err: HidError) -> Self {
+        Error::Hid(err)
+    }
+}
+
+impl From<CommandError> for Error {
+    fn from(err: CommandError) -> Self {
+        Error::Protocol(err)
+    }
+}
+
+/// Moves raw HID-IO packets to and from a keyboard
+pub trait Transport {
+    /// send a single serialized packet(at most PACKET_LEN bytes)
+    fn send(&mut self, packet: &[u8]) -> Result<(), Error>;
+    /// receive a single packet, returns None if nothing arrived before the timeout
+    fn recv(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error>;
+}
+
+/// Transport over the HID-IO interface of a real keyboard
+pub struct HidTransport {
+    device: HidDevice,
+}
+
+impl HidTransport {
+    /// open the first ErgoOne HID-IO interface found
+    pub fn open() -> Result<Self, Error> {
+        let api = HidApi::new()?;
+        let info = api
+            .device_list()
+            .find(|d| {
+                d.vendor_id() == VID && d.product_id() == PID && d.usage_page() == HIDIO_USAGE_PAGE
+            })
+            .ok_or(Error::NotFound)?;
+        Ok(HidTransport {
+            device: info.open_device(&api)?,
+        })
+    }
+}
+
+impl Transport for HidTransport {
+    fn send(&mut self, packet: &[u8]) -> Result<(), Error> {
+        // first byte is the report id, HID-IO doesn't use one
+        let mut report = [0u8; PACKET_LEN + 1];
+        report[1..packet.len() + 1].copy_from_slice(packet);
+        self.device.write(&report)?;
+        Ok(())
+    }
+
+    fn recv(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, Error> {
+        let mut buf = [0u8; PACKET_LEN];
+        match self
+            .device
+            .read_timeout(&mut buf, timeout.as_millis() as i32)?
+        {
+            0 => Ok(None),
+            len => Ok(Some(buf[..len].to_vec())),
+        }
+    }
+}
@@ -3,5 +3,6 @@ pub enum CallbackActions {
     Press,
     Release,
     RGBSet,
+    RGBKeySet,
     SendString,
 }
@@ -0,0 +1,158 @@
+#[cfg(not(any(feature = "pio-scan", feature = "direct-pins")))]
+use crate::scanner::DiodeDirection;
+#[cfg(not(feature = "pio-scan"))]
+use crate::scanner::Sense;
+
+// Everything that changes between PCBs, a revised board gets its own copy of these
+
+pub const MATRIX_ROWS: usize = 5;
+pub const MATRIX_COLS: usize = 16;
+
+/// The ErgoOne diodes go from the columns to the rows, row2col is for boards with them the
+/// other way round
+#[cfg(not(any(feature = "pio-scan", feature = "direct-pins", feature = "row2col")))]
+pub const DIODES: DiodeDirection = DiodeDirection::Col2Row;
+#[cfg(all(feature = "row2col", not(feature = "direct-pins")))]
+pub const DIODES: DiodeDirection = DiodeDirection::Row2Col;
+/// The rows have no pulls on the board so they get drained before every strobe,
+/// sense-pull-up and sense-pull-down are for boards that use the internal pulls.
+/// The PIO program always drains, so pio-scan has no use for either
+#[cfg(not(any(
+    feature = "pio-scan",
+    feature = "sense-pull-up",
+    feature = "sense-pull-down"
+)))]
+pub const SENSE: Sense = Sense::Floating;
+#[cfg(feature = "sense-pull-up")]
+pub const SENSE: Sense = Sense::PullUp;
+#[cfg(feature = "sense-pull-down")]
+pub const SENSE: Sense = Sense::PullDown;
+/// Hold back switches that could be ghosts, for boards without diodes or to track down a
+/// failed one. The ErgoOne has diodes so a rectangle of presses is a real chord
+pub const GHOST_CHECK: bool = false;
+/// Give switches flagged as chattering a longer debounce on release
+pub const CHATTER_DEBOUNCE: bool = true;
+
+/// (row pins, column pins) of the matrix in order, takes the rp2040_hal Pins
+/// A macro since pins can only be picked out of Pins by name
+#[cfg(not(any(feature = "split", feature = "direct-pins")))]
+macro_rules! matrix_pins {
+    ($pins:ident) => {
+        (
+            [
+                $pins.gpio15.into(),
+                $pins.gpio14.into(),
+                $pins.gpio13.into(),
+                $pins.gpio12.into(),
+                $pins.gpio11.into(),
+            ],
+            [
+                $pins.gpio29.into(),
+                $pins.gpio28.into(),
+                $pins.gpio27.into(),
+                $pins.gpio26.into(),
+                $pins.gpio25.into(),
+                $pins.gpio24.into(),
+                $pins.gpio23.into(),
+                $pins.gpio17.into(),
+                $pins.gpio16.into(),
+                $pins.gpio6.into(),
+                $pins.gpio5.into(),
+                $pins.gpio4.into(),
+                $pins.gpio3.into(),
+                $pins.gpio2.into(),
+                $pins.gpio1.into(),
+                $pins.gpio0.into(),
+            ],
+        )
+    };
+}
+#[cfg(not(any(feature = "split", feature = "direct-pins")))]
+pub(crate) use matrix_pins;
+
+/// The pin of every switch by [row][col] for boards without a matrix, None where there's no
+/// switch. The ErgoOne is a matrix, this is a two key example on its first column pins
+#[cfg(feature = "direct-pins")]
+macro_rules! direct_pins {
+    ($pins:ident) => {{
+        let mut direct: [[Option<DynPin>; MATRIX_COLS]; MATRIX_ROWS] =
+            core::array::from_fn(|_| core::array::from_fn(|_| None));
+        direct[0][0] = Some($pins.gpio29.into());
+        direct[0][1] = Some($pins.gpio28.into());
+        direct
+    }};
+}
+#[cfg(feature = "direct-pins")]
+pub(crate) use direct_pins;
+
+/// How each half of a split finds out whether it is the left or the right one, by USB
+/// unless the hand-strap or hand-flash feature picks another way
+#[cfg(all(
+    feature = "split",
+    not(any(feature = "hand-strap", feature = "hand-flash"))
+))]
+pub const HAND_FROM: crate::split::hand::HandSource = crate::split::hand::HandSource::Usb;
+#[cfg(feature = "hand-strap")]
+pub const HAND_FROM: crate::split::hand::HandSource = crate::split::hand::HandSource::Strap;
+#[cfg(feature = "hand-flash")]
+pub const HAND_FROM: crate::split::hand::HandSource = crate::split::hand::HandSource::Flash;
+/// Columns on each half of a split
+#[cfg(feature = "split")]
+pub const HALF_COLS: usize = MATRIX_COLS / 2;
+
+/// (row pins, column pins) of one half of a split, the same on both halves
+/// The columns go from the outside in, like the left half of the ErgoOne
+#[cfg(feature = "split")]
+macro_rules! half_matrix_pins {
+    ($pins:ident) => {
+        (
+            [
+                $pins.gpio15.into(),
+                $pins.gpio14.into(),
+                $pins.gpio13.into(),
+                $pins.gpio12.into(),
+                $pins.gpio11.into(),
+            ],
+            [
+                $pins.gpio29.into(),
+                $pins.gpio28.into(),
+                $pins.gpio27.into(),
+                $pins.gpio26.into(),
+                $pins.gpio25.into(),
+                $pins.gpio24.into(),
+                $pins.gpio23.into(),
+                $pins.gpio17.into(),
+            ],
+        )
+    };
+}
+#[cfg(feature = "split")]
+pub(crate) use half_matrix_pins;
+
+/// The pin read for HandSource::Strap
+#[cfg(feature = "hand-strap")]
+macro_rules! hand_strap {
+    ($pins:ident) => {
+        $pins.gpio10.into()
+    };
+}
+#[cfg(feature = "hand-strap")]
+pub(crate) use hand_strap;
+
+/// Rotary encoders on the board, see encoder_pins!
+#[cfg(feature = "encoders")]
+pub const ENCODERS: usize = 1;
+/// Pin changes per detent of the encoders, 4 for most, some click every 2 or every 1
+#[cfg(feature = "encoders")]
+pub const ENCODER_RESOLUTION: u8 = 4;
+
+/// (a, b) pins of every encoder, swap them if an encoder turns the wrong way
+/// The ErgoOne has none, these are free pins to wire one to
+#[cfg(feature = "encoders")]
+macro_rules! encoder_pins {
+    ($pins:ident) => {
+        [($pins.gpio20.into(), $pins.gpio21.into())]
+    };
+}
+#[cfg(feature = "encoders")]
+pub(crate) use encoder_pins;
@@ -0,0 +1,40 @@
+use defmt::Format;
+
+/// Things that can be done by holding a key while the keyboard is plugged in
+#[derive(Copy, Clone, PartialEq, Debug, Format)]
+pub enum BootAction {
+    /// reboot into the USB mass storage bootloader
+    Bootloader,
+    /// erase everything stored in the settings flash sector
+    ClearSettings,
+    /// start with the LEDs turned off
+    LedsOff,
+    /// only ever send boot protocol(6KRO) keyboard reports
+    BootProtocol,
+    /// use ERGOONE_QWERTY instead of the default layout
+    Qwerty,
+    /// start in the matrix test(see matrix_test)
+    MatrixTest,
+}
+
+/// (row, col, action) zero indexed matrix positions checked on boot
+/// positions are named after the key they hold in ERGOONE_RSTLNE
+#[rustfmt::skip]
+pub const BOOT_ACTIONS: [(usize, usize, BootAction); 6] = [
+    (0, 0,  BootAction::Bootloader),    // Sym_Tild
+    (0, 15, BootAction::ClearSettings), // Sym_Equz
+    (2, 14, BootAction::LedsOff),       // Ltr_Lzzz
+    (3, 10, BootAction::BootProtocol),  // Ltr_Bzzz
+    (1, 1,  BootAction::Qwerty),        // Ltr_Qzzz
+    (2, 5,  BootAction::MatrixTest),    // Ltr_Tzzz
+];
+
+/// get every boot action whose key is held in the scanned matrix state
+pub fn held_actions<const RSIZE: usize, const CSIZE: usize>(
+    scan: &[[bool; CSIZE]; RSIZE],
+) -> impl Iterator<Item = BootAction> + '_ {
+    BOOT_ACTIONS
+        .iter()
+        .filter(|(r, c, _)| scan.get(*r).and_then(|row| row.get(*c)) == Some(&true))
+        .map(|(_, _, a)| *a)
+}
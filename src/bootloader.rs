@@ -0,0 +1,81 @@
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use defmt::info;
+
+/// How long a "bootloader arm" HID-IO command stays valid for
+const ARM_TIMEOUT_MS: u32 = 5000;
+
+/// Set when a key or HID-IO asks to reboot into the USB bootloader
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+/// Time(ms) the HID-IO flash mode command was armed at, 0 when not armed
+static ARMED_AT: AtomicU32 = AtomicU32::new(0);
+
+/// ask the main loop to release all keys and reboot into the bootloader
+pub fn request() {
+    info!("Bootloader requested");
+    REQUESTED.store(true, Ordering::Relaxed);
+}
+
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
+
+/// allow the next HID-IO flash mode command within ARM_TIMEOUT_MS
+pub fn arm(now: u32) {
+    ARMED_AT.store(now.max(1), Ordering::Relaxed);
+}
+
+/// consume the armed state, returns true if it was armed and hasn't expired
+pub fn take_armed(now: u32) -> bool {
+    // no atomic swap on thumbv6m, arm() runs in the main loop and this in the USB interrupt
+    let armed = critical_section::with(|_| {
+        let armed = ARMED_AT.load(Ordering::Relaxed);
+        ARMED_AT.store(0, Ordering::Relaxed);
+        armed
+    });
+    armed != 0 && now.wrapping_sub(armed) <= ARM_TIMEOUT_MS
+}
+
+/// jump straight into the RP2040 USB mass storage bootloader
+pub fn enter() -> ! {
+    // let gpio_activity_pin_mask = 0;
+    // let disable_interface_mask = 0;
+    // rp2040_hal::rom_data::reset_to_usb_boot(gpio_activity_pin_mask, disable_interface_mask);
+    rp2040_hal::rom_data::reset_to_usb_boot(0, 0);
+    #[allow(clippy::empty_loop)]
+    loop {}
+}
+
+#[cfg(feature = "double-tap-reset")]
+mod double_tap {
+    use core::mem::MaybeUninit;
+    use core::ptr::{read_volatile, write_volatile};
+
+    use defmt::info;
+
+    /// Window(ms) in which a second reset goes to the bootloader
+    const DOUBLE_TAP_WINDOW_MS: u32 = 500;
+    const DOUBLE_TAP_MAGIC: u32 = 0xf01681de;
+
+    /// Survives a reset since .uninit isn't zeroed on boot
+    #[link_section = ".uninit.DOUBLE_TAP"]
+    static mut DOUBLE_TAP: MaybeUninit<u32> = MaybeUninit::uninit();
+
+    /// go into the bootloader if the board was reset twice within DOUBLE_TAP_WINDOW_MS
+    pub fn check(delay: &mut cortex_m::delay::Delay) {
+        unsafe {
+            let flag = DOUBLE_TAP.as_mut_ptr();
+            if read_volatile(flag) == DOUBLE_TAP_MAGIC {
+                write_volatile(flag, 0);
+                info!("Double tap reset detected, going into bootloader mode.");
+                super::enter();
+            }
+            write_volatile(flag, DOUBLE_TAP_MAGIC);
+            delay.delay_ms(DOUBLE_TAP_WINDOW_MS);
+            write_volatile(flag, 0);
+        }
+    }
+}
+
+#[cfg(feature = "double-tap-reset")]
+pub use double_tap::check as check_double_tap;
@@ -0,0 +1,184 @@
+use defmt::{debug, Format};
+use embedded_hal::digital::v2::InputPin;
+use rp2040_hal::gpio::DynPin;
+
+use crate::key::{Key, DEBOUNCE_CYCLES};
+use crate::keyscanning::run_key;
+use crate::{matrix_test, millis, rgb, Context};
+
+/// Polls the key of a step stays down, long enough to get past the key debounce
+const STEP_CYCLES: u16 = DEBOUNCE_CYCLES + 1;
+/// Steps waiting to be sent per direction, a faster spin than the keys can keep up with
+/// drops the rest
+const MAX_PENDING: u8 = 8;
+
+/// Which way an encoder turned, as seen with the pins in the order of board::encoder_pins!
+#[derive(Copy, Clone, PartialEq, Debug, Format)]
+pub enum Direction {
+    Cw = 0,
+    Ccw = 1,
+}
+
+/// Turns the two pins of an encoder into steps
+/// Every change of a pin is a quarter of a full quadrature cycle, resolution of them make a
+/// step(usually one detent)
+pub struct Quadrature {
+    /// the pins as of the last update, a in bit 1 and b in bit 0
+    state: u8,
+    /// quarter steps since the last step, negative counter-clockwise
+    count: i8,
+    resolution: i8,
+}
+
+impl Quadrature {
+    /// resolution is the pin changes per step(1-127), 4 for most detented encoders
+    pub const fn new(resolution: u8) -> Self {
+        assert!(
+            resolution >= 1 && resolution <= i8::MAX as u8,
+            "encoder resolution out of range"
+        );
+        Quadrature {
+            state: 0b11,
+            count: 0,
+            resolution: resolution as i8,
+        }
+    }
+
+    /// feed the pins, returns the direction once a whole step turned
+    pub fn update(&mut self, a: bool, b: bool) -> Option<Direction> {
+        // (old state, new state) to quarter steps, both pins changing at once is a missed
+        // update and counts for nothing
+        const TRANSITIONS: [i8; 16] = [0, -1, 1, 0, 1, 0, 0, -1, -1, 0, 0, 1, 0, 1, -1, 0];
+        let state = (a as u8) << 1 | b as u8;
+        self.count += TRANSITIONS[(self.state << 2 | state) as usize];
+        self.state = state;
+        if self.count >= self.resolution {
+            self.count -= self.resolution;
+            Some(Direction::Cw)
+        } else if self.count <= -self.resolution {
+            self.count += self.resolution;
+            Some(Direction::Ccw)
+        } else {
+            None
+        }
+    }
+}
+
+/// Rotary encoders whose steps tap keys from the keymap, one key per direction and layer
+/// A step presses its key like a switch closing for STEP_CYCLES polls and then opening, so
+/// every key type works on an encoder the same as in the matrix
+pub struct Encoders<const N: usize, const L: usize> {
+    /// (a, b) of each encoder
+    pins: [(DynPin, DynPin); N],
+    decoders: [Quadrature; N],
+    /// [layer][encoder][direction]
+    keys: [[[Key; 2]; N]; L],
+    /// steps waiting to be sent, [encoder][direction]
+    pending: [[u8; 2]; N],
+    /// (layer, polls since it started) of the step being sent, [encoder][direction]
+    taps: [[Option<(usize, u16)>; 2]; N],
+}
+
+impl<const N: usize, const L: usize> Encoders<N, L> {
+    /// the pins get pull-ups, the common pin of each encoder goes to ground
+    pub fn new(mut pins: [(DynPin, DynPin); N], resolution: u8, keys: [[[Key; 2]; N]; L]) -> Self {
+        for (a, b) in pins.iter_mut() {
+            a.into_pull_up_input();
+            b.into_pull_up_input();
+        }
+        Encoders {
+            pins,
+            decoders: [(); N].map(|_| Quadrature::new(resolution)),
+            keys,
+            pending: [[0; 2]; N],
+            taps: [[None; 2]; N],
+        }
+    }
+
+    /// read the encoders and run their keys, called every poll like Matrix::poll
+    /// the layer in ctx picks the keys for new steps, a layer without keys uses layer 0
+    pub fn poll(&mut self, ctx: Context) {
+        let layer = ctx.layer as usize;
+        let layer = if layer < L { layer } else { 0 };
+        for e in 0..N {
+            let (a, b) = &self.pins[e];
+            let step = self.decoders[e].update(a.is_high().unwrap(), b.is_high().unwrap());
+            // the test only wants the matrix, a turn shouldn't type anything
+            if let Some(dir) = step.filter(|_| !matrix_test::is_active()) {
+                debug!("Encoder {} turned {}", e, dir);
+                let pending = &mut self.pending[e][dir as usize];
+                *pending = (*pending + 1).min(MAX_PENDING);
+                rgb::key_activity(millis());
+            }
+            for d in 0..2 {
+                if self.taps[e][d].is_none() && self.pending[e][d] > 0 {
+                    self.pending[e][d] -= 1;
+                    self.taps[e][d] = Some((layer, 0));
+                }
+            }
+        }
+        // keys of another layer can still be finishing a step when the layer changes
+        for (l, layer_keys) in self.keys.iter_mut().enumerate() {
+            for (e, encoder_keys) in layer_keys.iter_mut().enumerate() {
+                for (d, key) in encoder_keys.iter_mut().enumerate() {
+                    let closed =
+                        matches!(self.taps[e][d], Some((tl, n)) if tl == l && n < STEP_CYCLES);
+                    if closed || !key.is_settled() {
+                        run_key(key, closed, ctx);
+                    }
+                }
+            }
+        }
+        // after STEP_CYCLES the key stays open for two polls, one to release and one to
+        // settle, before the next step can close it
+        for tap in self.taps.iter_mut().flatten() {
+            *tap = match *tap {
+                Some((l, n)) if n <= STEP_CYCLES => Some((l, n + 1)),
+                _ => None,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Direction, Quadrature};
+
+    /// (a, b) of a full clockwise cycle, starting from the resting 11
+    const CW: [(bool, bool); 4] = [(false, true), (false, false), (true, false), (true, true)];
+
+    fn feed(q: &mut Quadrature, pins: impl Iterator<Item = (bool, bool)>) -> Vec<Direction> {
+        pins.filter_map(|(a, b)| q.update(a, b)).collect()
+    }
+
+    #[test]
+    fn full_cycle_is_one_step() {
+        let mut q = Quadrature::new(4);
+        assert_eq!(feed(&mut q, CW.into_iter()), [Direction::Cw]);
+        // the reverse ends on 11 as well
+        let ccw = CW.into_iter().rev().skip(1).chain([(true, true)]);
+        assert_eq!(feed(&mut q, ccw), [Direction::Ccw]);
+    }
+
+    #[test]
+    fn resolution_sets_the_changes_per_step() {
+        let mut q = Quadrature::new(2);
+        assert_eq!(feed(&mut q, CW.into_iter()), [Direction::Cw, Direction::Cw]);
+        let mut q = Quadrature::new(1);
+        assert_eq!(feed(&mut q, CW.into_iter()), [Direction::Cw; 4]);
+    }
+
+    #[test]
+    fn both_pins_changing_counts_nothing() {
+        let mut q = Quadrature::new(1);
+        assert_eq!(feed(&mut q, [(false, false), (true, true)].into_iter()), []);
+        // and it picks up from where the pins are now
+        assert_eq!(feed(&mut q, CW.into_iter().take(1)), [Direction::Cw]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn resolution_must_fit_an_i8() {
+        Quadrature::new(128);
+    }
+}
@@ -0,0 +1,194 @@
+use defmt::{warn, Format};
+use heapless::{Deque, String, Vec};
+use kiibohd_hid_io::{h0016, h0031, KiibohdCommandInterface};
+
+use crate::rgb::{Effect, Hsv, NUM_LEDS};
+#[cfg(feature = "split")]
+use crate::split::hand::Hand;
+use crate::{bootloader, millis};
+
+/// Maximum number of terminal commands that can wait for the main loop
+const HIDIO_CMD_QUEUE_SIZE: usize = 4;
+
+/// Terminal commands received from the host over HID-IO(h0031)
+/// These are handled in the main loop since they need access to the matrix
+#[derive(Clone, PartialEq, Debug, Format)]
+pub enum HidioCmd {
+    /// dump every keymap entry as "row col entry", finished by "end"
+    Keymap,
+    /// dump every key's press count as "row col count", finished by "end"
+    Stats,
+    /// zero every press count
+    StatsClear,
+    /// dump the chatter count of every switch that chattered as "row col count",
+    /// flagged switches end in " worn", finished by "end"
+    Chatter,
+    /// forget all chatter
+    ChatterClear,
+    /// reply with the current words per minute
+    Wpm,
+    /// replace the key at row, col(zero indexed) with the given keymap entry
+    SetKey {
+        row: usize,
+        col: usize,
+        entry: String<40>,
+    },
+    /// set the color of the LEDs
+    Rgb { r: u8, g: u8, b: u8 },
+    /// set the color of the LEDs as hue, saturation and value
+    Hsv(Hsv),
+    /// switch the LED effect
+    Effect(Effect),
+    /// set the LED effect speed
+    Speed(u8),
+    /// make the LED effect speed follow the typing speed
+    SpeedWpm,
+    /// set the LED brightness
+    Brightness(u8),
+    /// seconds without key activity before the LEDs fade out, 0 keeps them on
+    IdleTimeout(u16),
+    /// allow or stop the key at row, col(zero indexed) waking the host from suspend
+    Wakeup {
+        row: usize,
+        col: usize,
+        enabled: bool,
+    },
+    /// show these (r, g, b) colors on the LEDs instead of the effect
+    Frame([(u8, u8, u8); NUM_LEDS]),
+    /// start or stop the matrix test, switches are then reported as "down row col" and
+    /// "up row col" instead of typing
+    MatrixTest(bool),
+    /// reply with the hand of this half and where it came from, e.g. "left usb"
+    #[cfg(feature = "split")]
+    Hand,
+    /// save the hand of this half to flash, used from the next boot on
+    #[cfg(feature = "split")]
+    SetHand(Hand),
+    /// allow the next flash mode(h0016) command to reboot into the bootloader
+    BootloaderArm,
+}
+
+/// Multi line replies sent a key at a time from the main loop
+#[derive(Copy, Clone, PartialEq, Debug, Format)]
+pub enum Dump {
+    Keymap,
+    Stats,
+    Chatter,
+}
+
+impl HidioCmd {
+    /// parse a terminal command string
+    /// e.g. "keymap", "setkey 0 1 df,Ltr_Azzz", "rgb 255 0 0", "effect rainbow", "bootloader arm"
+    /// "frame" takes one hex string holding rrggbb for every LED
+    pub fn parse(cmd: &str) -> Option<Self> {
+        // this runs in the USB interrupt, anything too long is rejected instead of panicking
+        let mut args: Vec<&str, 5> = Vec::new();
+        for arg in cmd.split_whitespace() {
+            args.push(arg).ok()?;
+        }
+        match args.as_slice() {
+            ["keymap"] => Some(HidioCmd::Keymap),
+            ["stats"] => Some(HidioCmd::Stats),
+            ["stats", "clear"] => Some(HidioCmd::StatsClear),
+            ["chatter"] => Some(HidioCmd::Chatter),
+            ["chatter", "clear"] => Some(HidioCmd::ChatterClear),
+            ["wpm"] => Some(HidioCmd::Wpm),
+            ["setkey", row, col, entry] => {
+                let mut key = String::new();
+                key.push_str(entry).ok()?;
+                Some(HidioCmd::SetKey {
+                    row: row.parse().ok()?,
+                    col: col.parse().ok()?,
+                    entry: key,
+                })
+            }
+            ["rgb", r, g, b] => Some(HidioCmd::Rgb {
+                r: r.parse().ok()?,
+                g: g.parse().ok()?,
+                b: b.parse().ok()?,
+            }),
+            ["hsv", h, s, v] => Some(HidioCmd::Hsv(Hsv::new(
+                h.parse().ok()?,
+                s.parse().ok()?,
+                v.parse().ok()?,
+            ))),
+            ["effect", name] => Effect::from_name(name).map(HidioCmd::Effect),
+            ["speed", "wpm"] => Some(HidioCmd::SpeedWpm),
+            ["speed", speed] => Some(HidioCmd::Speed(speed.parse().ok()?)),
+            ["brightness", brightness] => Some(HidioCmd::Brightness(brightness.parse().ok()?)),
+            ["idle", seconds] => Some(HidioCmd::IdleTimeout(seconds.parse().ok()?)),
+            ["wakeup", row, col, enabled @ ("on" | "off")] => Some(HidioCmd::Wakeup {
+                row: row.parse().ok()?,
+                col: col.parse().ok()?,
+                enabled: *enabled == "on",
+            }),
+            ["frame", hex] => parse_frame(hex).map(HidioCmd::Frame),
+            ["matrixtest", on @ ("on" | "off")] => Some(HidioCmd::MatrixTest(*on == "on")),
+            #[cfg(feature = "split")]
+            ["hand"] => Some(HidioCmd::Hand),
+            #[cfg(feature = "split")]
+            ["hand", "left"] => Some(HidioCmd::SetHand(Hand::Left)),
+            #[cfg(feature = "split")]
+            ["hand", "right"] => Some(HidioCmd::SetHand(Hand::Right)),
+            ["bootloader", "arm"] => Some(HidioCmd::BootloaderArm),
+            _ => None,
+        }
+    }
+}
+
+/// parse "rrggbb" hex for every LED, e.g. "ff000000ff00..."
+fn parse_frame(hex: &str) -> Option<[(u8, u8, u8); NUM_LEDS]> {
+    if hex.len() != NUM_LEDS * 6 || !hex.is_ascii() {
+        return None;
+    }
+    let byte = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok();
+    let mut frame = [(0, 0, 0); NUM_LEDS];
+    for (i, led) in frame.iter_mut().enumerate() {
+        *led = (byte(i * 3)?, byte(i * 3 + 1)?, byte(i * 3 + 2)?);
+    }
+    Some(frame)
+}
+
+pub struct HidioInterface<const H: usize> {
+    /// terminal commands waiting to be handled by the main loop
+    pub cmds: Deque<HidioCmd, HIDIO_CMD_QUEUE_SIZE>,
+}
+
+impl<const H: usize> HidioInterface<H> {
+    pub fn new() -> Self {
+        Self { cmds: Deque::new() }
+    }
+}
+
+impl<const H: usize> KiibohdCommandInterface<H> for HidioInterface<H> {
+    fn h0001_device_name(&self) -> Option<&str> {
+        Some("ErgoOne")
+    }
+
+    fn h0001_firmware_name(&self) -> Option<&str> {
+        Some("ErgoOne")
+    }
+
+    /// Only reboots if "bootloader arm" was sent shortly before
+    fn h0016_flashmode_cmd(&mut self, _data: h0016::Cmd) -> Result<h0016::Ack, h0016::Nak> {
+        if bootloader::take_armed(millis()) {
+            bootloader::request();
+            Ok(h0016::Ack { scancode: 0 })
+        } else {
+            warn!("Flash mode requested without arming first");
+            Err(h0016::Nak {
+                error: h0016::Error::Disabled,
+            })
+        }
+    }
+
+    fn h0031_terminalinput(&mut self, data: h0031::Cmd<H>) -> bool {
+        match HidioCmd::parse(data.command.as_str()) {
+            Some(cmd) => self.cmds.push_back(cmd).is_ok(),
+            None => {
+                warn!("Unknown HID-IO command {}", data.command.as_str());
+                false
+            }
+        }
+    }
+}
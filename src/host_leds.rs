@@ -0,0 +1,185 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use defmt::{info, Format};
+use heapless::spsc::Consumer;
+use kiibohd_usb::LedState;
+
+/// Lock LED bitmask last set by the host, bit n-1 is LED id n
+static HOST_LEDS: AtomicU8 = AtomicU8::new(0);
+
+/// Caps Lock in the HID LED usage page, LED id 2
+const CAPS_LOCK: u8 = 1 << 1;
+
+/// Snapshot of the host lock LEDs handed to key behaviors through the Context
+#[derive(Copy, Clone, PartialEq, Debug, Default, Format)]
+pub struct HostLeds(u8);
+
+impl HostLeds {
+    pub fn caps_lock(&self) -> bool {
+        self.0 & CAPS_LOCK != 0
+    }
+
+    /// bit id - 1 is set for every LED that's on
+    #[cfg(feature = "split")]
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+}
+
+/// apply the LED changes HidInterface::pull queued, only called from the USB interrupt
+/// right after pull
+/// pull unwraps every enqueue and one report can change all 5 LEDs, so the queue is only
+/// sure to have room if it is emptied after every pull, from the main loop two reports in
+/// a row could fill it and panic inside the interrupt
+pub fn drain<const N: usize>(leds: &mut Consumer<'_, LedState, N>) {
+    while let Some(state) = leds.dequeue() {
+        update(state);
+    }
+}
+
+/// apply an LED change sent by the host
+fn update(state: LedState) {
+    // thumbv6m has no atomic read-modify-write, fine with a single writer
+    let leds = HOST_LEDS.load(Ordering::Relaxed);
+    match state {
+        LedState::Activate(id @ 1..=8) => HOST_LEDS.store(leds | 1 << (id - 1), Ordering::Relaxed),
+        LedState::Deactivate(id @ 1..=8) => {
+            HOST_LEDS.store(leds & !(1 << (id - 1)), Ordering::Relaxed)
+        }
+        _ => return,
+    }
+    info!("Host LEDs {}", get());
+}
+
+/// replace every LED at once, e.g. with the state the primary half of a split got
+#[cfg(feature = "split")]
+pub fn set(bits: u8) {
+    if HOST_LEDS.load(Ordering::Relaxed) != bits {
+        HOST_LEDS.store(bits, Ordering::Relaxed);
+        info!("Host LEDs {}", get());
+    }
+}
+
+/// current host LED state, safe to call from either core
+pub fn get() -> HostLeds {
+    HostLeds(HOST_LEDS.load(Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    use heapless::spsc::Queue;
+    use kiibohd_usb::{CtrlState, HidInterface, KeyState, LedState, MouseState};
+    use usb_device::bus::PollResult;
+    use usb_device::class_prelude::{EndpointAddress, EndpointType, UsbBus, UsbBusAllocator};
+    use usb_device::prelude::{UsbDeviceBuilder, UsbVidPid};
+    use usb_device::{Result, UsbDirection};
+    use usbd_hid::hid_class::HidCountryCode;
+
+    use super::{drain, get};
+
+    /// (setup, packet) the host sent to endpoint 0
+    static EP0_OUT: Mutex<VecDeque<(bool, Vec<u8>)>> = Mutex::new(VecDeque::new());
+
+    /// Bus with a host that only sends control transfers, see EP0_OUT
+    #[derive(Default)]
+    struct FakeBus {
+        next_ep: [u8; 2],
+    }
+
+    impl UsbBus for FakeBus {
+        fn alloc_ep(
+            &mut self,
+            ep_dir: UsbDirection,
+            ep_addr: Option<EndpointAddress>,
+            _: EndpointType,
+            _: u16,
+            _: u8,
+        ) -> Result<EndpointAddress> {
+            Ok(ep_addr.unwrap_or_else(|| {
+                let next = &mut self.next_ep[ep_dir as usize >> 7];
+                *next += 1;
+                EndpointAddress::from_parts(*next as usize, ep_dir)
+            }))
+        }
+
+        fn enable(&mut self) {}
+        fn reset(&self) {}
+        fn set_device_address(&self, _: u8) {}
+
+        fn write(&self, _: EndpointAddress, buf: &[u8]) -> Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn read(&self, ep_addr: EndpointAddress, buf: &mut [u8]) -> Result<usize> {
+            match EP0_OUT.lock().unwrap().pop_front() {
+                Some((_, packet)) if ep_addr.index() == 0 => {
+                    buf[..packet.len()].copy_from_slice(&packet);
+                    Ok(packet.len())
+                }
+                _ => Err(usb_device::UsbError::WouldBlock),
+            }
+        }
+
+        fn set_stalled(&self, _: EndpointAddress, _: bool) {}
+        fn is_stalled(&self, _: EndpointAddress) -> bool {
+            false
+        }
+        fn suspend(&self) {}
+        fn resume(&self) {}
+
+        fn poll(&self) -> PollResult {
+            match EP0_OUT.lock().unwrap().front() {
+                Some((setup, _)) => PollResult::Data {
+                    ep_out: !setup as u16,
+                    ep_in_complete: 0,
+                    ep_setup: *setup as u16,
+                },
+                None => PollResult::None,
+            }
+        }
+    }
+
+    #[test]
+    fn caps_lock_from_a_report() {
+        let alloc = UsbBusAllocator::new(FakeBus::default());
+        let mut kbd = Queue::<KeyState, 2>::new();
+        let mut led = Queue::<LedState, 6>::new();
+        let mut mouse = Queue::<MouseState, 2>::new();
+        let mut ctrl = Queue::<CtrlState, 2>::new();
+        let (led_producer, mut led_consumer) = led.split();
+        let mut hid = HidInterface::new(
+            &alloc,
+            HidCountryCode::US,
+            kbd.split().1,
+            led_producer,
+            mouse.split().1,
+            ctrl.split().1,
+        );
+        let mut dev = UsbDeviceBuilder::new(&alloc, UsbVidPid(0x16c0, 0x27dd)).build();
+
+        // SET_REPORT(output) to the NKRO keyboard on interface 1 and then the report
+        let set_report = |leds: u8| {
+            let mut packets = EP0_OUT.lock().unwrap();
+            packets.push_back((true, vec![0x21, 0x09, 0x00, 0x02, 0x01, 0x00, 0x01, 0x00]));
+            packets.push_back((false, vec![leds]));
+        };
+        let mut poll = || {
+            while dev.poll(&mut hid.interfaces()) {
+                hid.pull();
+                drain(&mut led_consumer);
+            }
+        };
+        set_report(0b010);
+        poll();
+        assert!(get().caps_lock());
+        assert_eq!(get().0, 0b010);
+        set_report(0b101);
+        poll();
+        assert!(!get().caps_lock());
+        // Num Lock and Scroll Lock
+        assert_eq!(get().0, 0b101);
+    }
+}
@@ -8,8 +8,9 @@ use defmt::{info, println};
 use crate::{key_codes::KeyCode, keyscanning::StateType};
 use crate::{Context, KeyImpl};
 
-pub(crate) const DEBOUNCE_CYCLES: u16 = 3;
-pub(crate) const HOLD_CYCLES: u16 = 20;
+// A cycle is one poll of the whole matrix, about 1ms
+pub(crate) const DEBOUNCE_CYCLES: u16 = 5;
+pub(crate) const HOLD_CYCLES: u16 = 300;
 
 // TODO impl idle tracking
 // const IDLE_CYCLES: u8 = 100;
@@ -38,6 +39,16 @@ pub struct Key {
     pub typ: &'static str,
 }
 
+impl Key {
+    /// up, done changing state and with nothing pending for a later poll
+    pub fn is_settled(&self) -> bool {
+        !self.raw_state
+            && self.state == StateType::Off
+            && self.prevstate == StateType::Off
+            && !self.previnfo.contains(&true)
+    }
+}
+
 pub trait Default {
     fn new(KC1: KeyCode, KC2: Option<KeyCode>) -> Self
     where
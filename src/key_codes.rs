@@ -76,6 +76,10 @@ pub enum KeyCode {
     Sym_Perd,
     /// Forward Slash
     Sym_FSla,
+    /// Non-US Hash and Tilde
+    Sym_NUHs,
+    /// Non-US Backslash and Pipe
+    Sym_NUBs,
     /// Capslock
     Fun_Caps,
     Fun_F1zz,
@@ -90,6 +94,18 @@ pub enum KeyCode {
     Fun_F10z,
     Fun_F11z,
     Fun_F12z,
+    Fun_F13z,
+    Fun_F14z,
+    Fun_F15z,
+    Fun_F16z,
+    Fun_F17z,
+    Fun_F18z,
+    Fun_F19z,
+    Fun_F20z,
+    Fun_F21z,
+    Fun_F22z,
+    Fun_F23z,
+    Fun_F24z,
 
     /// Right
     Arw_Rght,
@@ -100,6 +116,14 @@ pub enum KeyCode {
     /// Up
     Arw_Upzz,
 
+    /// PrintScreen
+    Fun_PScr,
+    /// ScrollLock
+    Fun_SLck,
+    /// Pause
+    Fun_Paus,
+    /// Insert
+    Fun_Insz,
     /// Home
     Fun_Home,
     /// PageUp
@@ -119,11 +143,263 @@ pub enum KeyCode {
     /// Volume Down
     Vol_Down,
 
-    // Keypad keys
-    /// Left Paren
+    // Shifted symbols, sent as shift + the key in brackets
+    /// Exclamation Mark(Num_1zzz)
+    Sym_Excl,
+    /// At(Num_2zzz)
+    Sym_Atzz,
+    /// Hash(Num_3zzz)
+    Sym_Hash,
+    /// Dollar(Num_4zzz)
+    Sym_Dolr,
+    /// Percent(Num_5zzz)
+    Sym_Prct,
+    /// Caret(Num_6zzz)
+    Sym_Crtz,
+    /// Ampersand(Num_7zzz)
+    Sym_Ampr,
+    /// Asterisk(Num_8zzz)
+    Sym_Astr,
+    /// Left Paren(Num_9zzz)
     Sym_LPar,
-    /// Right Paren
+    /// Right Paren(Num_0zzz)
     Sym_RPar,
+    /// Underscore(Sym_Minz)
+    Sym_Unds,
+    /// Plus(Sym_Equz)
+    Sym_Plus,
+    /// Left Curly Brace(Sym_LBrk)
+    Sym_LBrc,
+    /// Right Curly Brace(Sym_RBrk)
+    Sym_RBrc,
+    /// Pipe(Sym_BSla)
+    Sym_Pipe,
+    /// Colon(Sym_Scln)
+    Sym_Coln,
+    /// Double Quote(Sym_SQut)
+    Sym_DQut,
+    /// Tilde(Sym_Tild)
+    Sym_TldS,
+    /// Less Than(Sym_Coma)
+    Sym_LThn,
+    /// Greater Than(Sym_Perd)
+    Sym_GThn,
+    /// Question Mark(Sym_FSla)
+    Sym_Ques,
+
+    // Keypad keys
+    /// NumLock
+    Fun_NLck,
+    /// Keypad Forward Slash
+    Kpd_Slsh,
+    /// Keypad Asterisk
+    Kpd_Astr,
+    /// Keypad Minus
+    Kpd_Minz,
+    /// Keypad Plus
+    Kpd_Plus,
+    /// Keypad Enter
+    Kpd_Entz,
+    Kpd_1zzz,
+    Kpd_2zzz,
+    Kpd_3zzz,
+    Kpd_4zzz,
+    Kpd_5zzz,
+    Kpd_6zzz,
+    Kpd_7zzz,
+    Kpd_8zzz,
+    Kpd_9zzz,
+    Kpd_0zzz,
+    /// Keypad Period
+    Kpd_Perd,
+    /// Keypad Equals
+    Kpd_Equz,
+    /// Keypad Comma
+    Kpd_Coma,
+    /// Keypad Equals(AS/400)
+    Kpd_EqAS,
+    /// Keypad 00
+    Kpd_00zz,
+    /// Keypad 000
+    Kpd_000z,
+    /// Thousands Separator
+    Kpd_ThSp,
+    /// Decimal Separator
+    Kpd_DcSp,
+    /// Currency Unit
+    Kpd_CrUn,
+    /// Currency Sub-unit
+    Kpd_CrSu,
+    /// Keypad Left Paren
+    Kpd_LPar,
+    /// Keypad Right Paren
+    Kpd_RPar,
+    /// Keypad Left Curly Brace
+    Kpd_LBrc,
+    /// Keypad Right Curly Brace
+    Kpd_RBrc,
+    /// Keypad Tab
+    Kpd_Tabz,
+    /// Keypad Backspace
+    Kpd_Bksp,
+    Kpd_Azzz,
+    Kpd_Bzzz,
+    Kpd_Czzz,
+    Kpd_Dzzz,
+    Kpd_Ezzz,
+    Kpd_Fzzz,
+    /// Keypad XOR
+    Kpd_Xorz,
+    /// Keypad Caret
+    Kpd_Crtz,
+    /// Keypad Percent
+    Kpd_Prct,
+    /// Keypad Less Than
+    Kpd_LThn,
+    /// Keypad Greater Than
+    Kpd_GThn,
+    /// Keypad Ampersand
+    Kpd_Ampr,
+    /// Keypad Double Ampersand
+    Kpd_DAmp,
+    /// Keypad Pipe
+    Kpd_Pipe,
+    /// Keypad Double Pipe
+    Kpd_DPip,
+    /// Keypad Colon
+    Kpd_Coln,
+    /// Keypad Hash
+    Kpd_Hash,
+    /// Keypad Space
+    Kpd_Spcz,
+    /// Keypad At
+    Kpd_Atzz,
+    /// Keypad Exclamation
+    Kpd_Excl,
+    /// Keypad Memory Store
+    Kpd_MSto,
+    /// Keypad Memory Recall
+    Kpd_MRcl,
+    /// Keypad Memory Clear
+    Kpd_MClr,
+    /// Keypad Memory Add
+    Kpd_MAdd,
+    /// Keypad Memory Subtract
+    Kpd_MSub,
+    /// Keypad Memory Multiply
+    Kpd_MMul,
+    /// Keypad Memory Divide
+    Kpd_MDiv,
+    /// Keypad Plus/Minus
+    Kpd_PlMn,
+    /// Keypad Clear
+    Kpd_Clrz,
+    /// Keypad Clear Entry
+    Kpd_ClrE,
+    /// Keypad Binary
+    Kpd_Binz,
+    /// Keypad Octal
+    Kpd_Octz,
+    /// Keypad Decimal
+    Kpd_Decz,
+    /// Keypad Hexadecimal
+    Kpd_Hexz,
+
+    // Application keys
+    /// Application(Menu)
+    Fun_Appl,
+    /// Power
+    Fun_Powr,
+    /// Execute
+    Fun_Exec,
+    /// Help
+    Fun_Help,
+    /// Menu
+    Fun_Menu,
+    /// Select
+    Fun_Selz,
+    /// Stop
+    Fun_Stop,
+    /// Again
+    Fun_Agan,
+    /// Undo
+    Fun_Undo,
+    /// Cut
+    Fun_Cutz,
+    /// Copy
+    Fun_Copy,
+    /// Paste
+    Fun_Pste,
+    /// Find
+    Fun_Find,
+    /// Locking Capslock
+    Fun_LCap,
+    /// Locking NumLock
+    Fun_LNum,
+    /// Locking ScrollLock
+    Fun_LScr,
+    /// Alternate Erase
+    Fun_AErs,
+    /// SysReq/Attention
+    Fun_SysR,
+    /// Cancel
+    Fun_Cncl,
+    /// Clear
+    Fun_Clrz,
+    /// Prior
+    Fun_Prir,
+    /// Return
+    Fun_Retn,
+    /// Separator
+    Fun_Sepr,
+    /// Out
+    Fun_Outz,
+    /// Oper
+    Fun_Oper,
+    /// Clear/Again
+    Fun_ClrA,
+    /// CrSel/Props
+    Fun_CrSl,
+    /// ExSel
+    Fun_ExSl,
+
+    // International keys
+    /// International1(Ro)
+    Int_1zzz,
+    /// International2(Katakana/Hiragana)
+    Int_2zzz,
+    /// International3(Yen)
+    Int_3zzz,
+    /// International4(Henkan)
+    Int_4zzz,
+    /// International5(Muhenkan)
+    Int_5zzz,
+    /// International6
+    Int_6zzz,
+    /// International7
+    Int_7zzz,
+    /// International8
+    Int_8zzz,
+    /// International9
+    Int_9zzz,
+    /// Lang1(Hangul/English)
+    Lng_1zzz,
+    /// Lang2(Hanja)
+    Lng_2zzz,
+    /// Lang3(Katakana)
+    Lng_3zzz,
+    /// Lang4(Hiragana)
+    Lng_4zzz,
+    /// Lang5(Zenkaku/Hankaku)
+    Lng_5zzz,
+    /// Lang6
+    Lng_6zzz,
+    /// Lang7
+    Lng_7zzz,
+    /// Lang8
+    Lng_8zzz,
+    /// Lang9
+    Lng_9zzz,
 
     // Modifier keys
     Mod_L01z,
@@ -146,6 +422,40 @@ pub enum KeyCode {
 
     Led_Col1,
     Led_Col2,
+    /// Next LED effect
+    Led_Next,
+    /// Previous LED effect
+    Led_Prev,
+    /// LED effect speed up
+    Led_SpUp,
+    /// LED effect speed down
+    Led_SpDn,
+    /// LED brightness up
+    Led_BrUp,
+    /// LED brightness down
+    Led_BrDn,
+    /// LED hue up
+    Led_HueU,
+    /// LED hue down
+    Led_HueD,
+    /// LED saturation up
+    Led_SatU,
+    /// LED saturation down
+    Led_SatD,
+    /// LED value up
+    Led_ValU,
+    /// LED value down
+    Led_ValD,
+
+    // Layer keys
+    /// Layer 1 while held
+    Lyr_Mo1z,
+    /// Switch to the next layer, from the last one back to layer 0
+    Lyr_Next,
+
+    // Firmware keys
+    /// Reboot into the USB bootloader
+    Fun_Boot,
 }
 
 impl KeyCode {
@@ -167,102 +477,63 @@ impl KeyCode {
     pub fn is_modifier(&self) -> bool {
         *self == KeyCode::Mod_L01z || self.modifier_bitmask().is_some()
     }
+
+    /// the key a shifted symbol is typed with, e.g. Sym_Excl -> Num_1zzz
+    pub fn shifted_base(&self) -> Option<KeyCode> {
+        match *self {
+            KeyCode::Sym_Excl => Some(KeyCode::Num_1zzz),
+            KeyCode::Sym_Atzz => Some(KeyCode::Num_2zzz),
+            KeyCode::Sym_Hash => Some(KeyCode::Num_3zzz),
+            KeyCode::Sym_Dolr => Some(KeyCode::Num_4zzz),
+            KeyCode::Sym_Prct => Some(KeyCode::Num_5zzz),
+            KeyCode::Sym_Crtz => Some(KeyCode::Num_6zzz),
+            KeyCode::Sym_Ampr => Some(KeyCode::Num_7zzz),
+            KeyCode::Sym_Astr => Some(KeyCode::Num_8zzz),
+            KeyCode::Sym_LPar => Some(KeyCode::Num_9zzz),
+            KeyCode::Sym_RPar => Some(KeyCode::Num_0zzz),
+            KeyCode::Sym_Unds => Some(KeyCode::Sym_Minz),
+            KeyCode::Sym_Plus => Some(KeyCode::Sym_Equz),
+            KeyCode::Sym_LBrc => Some(KeyCode::Sym_LBrk),
+            KeyCode::Sym_RBrc => Some(KeyCode::Sym_RBrk),
+            KeyCode::Sym_Pipe => Some(KeyCode::Sym_BSla),
+            KeyCode::Sym_Coln => Some(KeyCode::Sym_Scln),
+            KeyCode::Sym_DQut => Some(KeyCode::Sym_SQut),
+            KeyCode::Sym_TldS => Some(KeyCode::Sym_Tild),
+            KeyCode::Sym_LThn => Some(KeyCode::Sym_Coma),
+            KeyCode::Sym_GThn => Some(KeyCode::Sym_Perd),
+            KeyCode::Sym_Ques => Some(KeyCode::Sym_FSla),
+            _ => None,
+        }
+    }
+
+    /// keys handled by the firmware itself that are never sent to the host
+    pub fn is_firmware(&self) -> bool {
+        matches!(
+            *self,
+            KeyCode::Led_Col1
+                | KeyCode::Led_Col2
+                | KeyCode::Led_Next
+                | KeyCode::Led_Prev
+                | KeyCode::Led_SpUp
+                | KeyCode::Led_SpDn
+                | KeyCode::Led_BrUp
+                | KeyCode::Led_BrDn
+                | KeyCode::Led_HueU
+                | KeyCode::Led_HueD
+                | KeyCode::Led_SatU
+                | KeyCode::Led_SatD
+                | KeyCode::Led_ValU
+                | KeyCode::Led_ValD
+                | KeyCode::Lyr_Mo1z
+                | KeyCode::Lyr_Next
+                | KeyCode::Fun_Boot
+        )
+    }
 }
 
 impl From<&KeyCode> for u8 {
     fn from(val: &KeyCode) -> Self {
-        match val {
-            KeyCode::Ltr_Azzz => 0x04,
-            KeyCode::Ltr_Bzzz => 0x05,
-            KeyCode::Ltr_Czzz => 0x06,
-            KeyCode::Ltr_Dzzz => 0x07,
-            KeyCode::Ltr_Ezzz => 0x08,
-            KeyCode::Ltr_Fzzz => 0x09,
-            KeyCode::Ltr_Gzzz => 0x0a,
-            KeyCode::Ltr_Hzzz => 0x0b,
-            KeyCode::Ltr_Izzz => 0x0c,
-            KeyCode::Ltr_Jzzz => 0x0d,
-            KeyCode::Ltr_Kzzz => 0x0e,
-            KeyCode::Ltr_Lzzz => 0x0f,
-            KeyCode::Ltr_Mzzz => 0x10,
-            KeyCode::Ltr_Nzzz => 0x11,
-            KeyCode::Ltr_Ozzz => 0x12,
-            KeyCode::Ltr_Pzzz => 0x13,
-            KeyCode::Ltr_Qzzz => 0x14,
-            KeyCode::Ltr_Rzzz => 0x15,
-            KeyCode::Ltr_Szzz => 0x16,
-            KeyCode::Ltr_Tzzz => 0x17,
-            KeyCode::Ltr_Uzzz => 0x18,
-            KeyCode::Ltr_Vzzz => 0x19,
-            KeyCode::Ltr_Wzzz => 0x1a,
-            KeyCode::Ltr_Xzzz => 0x1b,
-            KeyCode::Ltr_Yzzz => 0x1c,
-            KeyCode::Ltr_Zzzz => 0x1d,
-            KeyCode::Num_1zzz => 0x1e,
-            KeyCode::Num_2zzz => 0x1f,
-            KeyCode::Num_3zzz => 0x20,
-            KeyCode::Num_4zzz => 0x21,
-            KeyCode::Num_5zzz => 0x22,
-            KeyCode::Num_6zzz => 0x23,
-            KeyCode::Num_7zzz => 0x24,
-            KeyCode::Num_8zzz => 0x25,
-            KeyCode::Num_9zzz => 0x26,
-            KeyCode::Num_0zzz => 0x27,
-            KeyCode::Fun_Entz => 0x28,
-            KeyCode::Fun_Escz => 0x29,
-            KeyCode::Fun_Bksp => 0x2a,
-            KeyCode::Fun_Tabz => 0x2b,
-            KeyCode::Fun_Spcz => 0x2c,
-            KeyCode::Sym_Minz => 0x2d,
-            KeyCode::Sym_Equz => 0x2e,
-            KeyCode::Sym_LBrk => 0x2f,
-            KeyCode::Sym_RBrk => 0x30,
-            KeyCode::Sym_BSla => 0x31,
-            KeyCode::Sym_Scln => 0x33,
-            KeyCode::Sym_SQut => 0x34,
-            KeyCode::Sym_Tild => 0x35,
-            KeyCode::Sym_Coma => 0x36,
-            KeyCode::Sym_Perd => 0x37,
-            KeyCode::Sym_FSla => 0x38,
-            KeyCode::Fun_Caps => 0x39,
-            KeyCode::Fun_F1zz => 0x3a,
-            KeyCode::Fun_F2zz => 0x3b,
-            KeyCode::Fun_F3zz => 0x3c,
-            KeyCode::Fun_F4zz => 0x3d,
-            KeyCode::Fun_F5zz => 0x3e,
-            KeyCode::Fun_F6zz => 0x3f,
-            KeyCode::Fun_F7zz => 0x40,
-            KeyCode::Fun_F8zz => 0x41,
-            KeyCode::Fun_F9zz => 0x42,
-            KeyCode::Fun_F10z => 0x43,
-            KeyCode::Fun_F11z => 0x44,
-            KeyCode::Fun_F12z => 0x45,
-            KeyCode::Arw_Rght => 0x4f,
-            KeyCode::Arw_Left => 0x50,
-            KeyCode::Arw_Down => 0x51,
-            KeyCode::Arw_Upzz => 0x52,
-            KeyCode::Fun_Home => 0x4a,
-            KeyCode::Fun_PgUp => 0x4b,
-            KeyCode::Fun_Delz => 0x4c,
-            KeyCode::Fun_Endz => 0x4d,
-            KeyCode::Fun_PgDn => 0x4e,
-            KeyCode::Vol_Mute => 0x7f,
-            KeyCode::Vol_Upzz => 0x80,
-            KeyCode::Vol_Down => 0x81,
-            KeyCode::Sym_LPar => 0xb6,
-            KeyCode::Sym_RPar => 0xb7,
-            KeyCode::Mod_L01z => 0xf0,
-            KeyCode::Mod_LSft => 0xe1,
-            KeyCode::Mod_LCtl => 0xe0,
-            KeyCode::Mod_LAlt => 0xe2,
-            KeyCode::Mod_LCmd => 0xe3,
-            KeyCode::Mod_RCmd => 0xe7,
-            KeyCode::Mod_RAlt => 0xe6,
-            KeyCode::Mod_RCtl => 0xe4,
-            KeyCode::Mod_RSft => 0xe5,
-            KeyCode::________ => 0x00,
-            _ => 0x00,
-        }
+        u8::from(*val)
     }
 }
 
@@ -321,6 +592,8 @@ impl From<KeyCode> for u8 {
             KeyCode::Sym_Coma => 0x36,
             KeyCode::Sym_Perd => 0x37,
             KeyCode::Sym_FSla => 0x38,
+            KeyCode::Sym_NUHs => 0x32,
+            KeyCode::Sym_NUBs => 0x64,
             KeyCode::Fun_Caps => 0x39,
             KeyCode::Fun_F1zz => 0x3a,
             KeyCode::Fun_F2zz => 0x3b,
@@ -334,10 +607,26 @@ impl From<KeyCode> for u8 {
             KeyCode::Fun_F10z => 0x43,
             KeyCode::Fun_F11z => 0x44,
             KeyCode::Fun_F12z => 0x45,
+            KeyCode::Fun_F13z => 0x68,
+            KeyCode::Fun_F14z => 0x69,
+            KeyCode::Fun_F15z => 0x6a,
+            KeyCode::Fun_F16z => 0x6b,
+            KeyCode::Fun_F17z => 0x6c,
+            KeyCode::Fun_F18z => 0x6d,
+            KeyCode::Fun_F19z => 0x6e,
+            KeyCode::Fun_F20z => 0x6f,
+            KeyCode::Fun_F21z => 0x70,
+            KeyCode::Fun_F22z => 0x71,
+            KeyCode::Fun_F23z => 0x72,
+            KeyCode::Fun_F24z => 0x73,
             KeyCode::Arw_Rght => 0x4f,
             KeyCode::Arw_Left => 0x50,
             KeyCode::Arw_Down => 0x51,
             KeyCode::Arw_Upzz => 0x52,
+            KeyCode::Fun_PScr => 0x46,
+            KeyCode::Fun_SLck => 0x47,
+            KeyCode::Fun_Paus => 0x48,
+            KeyCode::Fun_Insz => 0x49,
             KeyCode::Fun_Home => 0x4a,
             KeyCode::Fun_PgUp => 0x4b,
             KeyCode::Fun_Delz => 0x4c,
@@ -346,8 +635,139 @@ impl From<KeyCode> for u8 {
             KeyCode::Vol_Mute => 0x7f,
             KeyCode::Vol_Upzz => 0x80,
             KeyCode::Vol_Down => 0x81,
-            KeyCode::Sym_LPar => 0xb6,
-            KeyCode::Sym_RPar => 0xb7,
+            KeyCode::Sym_Excl => 0x1e,
+            KeyCode::Sym_Atzz => 0x1f,
+            KeyCode::Sym_Hash => 0x20,
+            KeyCode::Sym_Dolr => 0x21,
+            KeyCode::Sym_Prct => 0x22,
+            KeyCode::Sym_Crtz => 0x23,
+            KeyCode::Sym_Ampr => 0x24,
+            KeyCode::Sym_Astr => 0x25,
+            KeyCode::Sym_LPar => 0x26,
+            KeyCode::Sym_RPar => 0x27,
+            KeyCode::Sym_Unds => 0x2d,
+            KeyCode::Sym_Plus => 0x2e,
+            KeyCode::Sym_LBrc => 0x2f,
+            KeyCode::Sym_RBrc => 0x30,
+            KeyCode::Sym_Pipe => 0x31,
+            KeyCode::Sym_Coln => 0x33,
+            KeyCode::Sym_DQut => 0x34,
+            KeyCode::Sym_TldS => 0x35,
+            KeyCode::Sym_LThn => 0x36,
+            KeyCode::Sym_GThn => 0x37,
+            KeyCode::Sym_Ques => 0x38,
+            KeyCode::Fun_NLck => 0x53,
+            KeyCode::Kpd_Slsh => 0x54,
+            KeyCode::Kpd_Astr => 0x55,
+            KeyCode::Kpd_Minz => 0x56,
+            KeyCode::Kpd_Plus => 0x57,
+            KeyCode::Kpd_Entz => 0x58,
+            KeyCode::Kpd_1zzz => 0x59,
+            KeyCode::Kpd_2zzz => 0x5a,
+            KeyCode::Kpd_3zzz => 0x5b,
+            KeyCode::Kpd_4zzz => 0x5c,
+            KeyCode::Kpd_5zzz => 0x5d,
+            KeyCode::Kpd_6zzz => 0x5e,
+            KeyCode::Kpd_7zzz => 0x5f,
+            KeyCode::Kpd_8zzz => 0x60,
+            KeyCode::Kpd_9zzz => 0x61,
+            KeyCode::Kpd_0zzz => 0x62,
+            KeyCode::Kpd_Perd => 0x63,
+            KeyCode::Kpd_Equz => 0x67,
+            KeyCode::Kpd_Coma => 0x85,
+            KeyCode::Kpd_EqAS => 0x86,
+            KeyCode::Kpd_00zz => 0xb0,
+            KeyCode::Kpd_000z => 0xb1,
+            KeyCode::Kpd_ThSp => 0xb2,
+            KeyCode::Kpd_DcSp => 0xb3,
+            KeyCode::Kpd_CrUn => 0xb4,
+            KeyCode::Kpd_CrSu => 0xb5,
+            KeyCode::Kpd_LPar => 0xb6,
+            KeyCode::Kpd_RPar => 0xb7,
+            KeyCode::Kpd_LBrc => 0xb8,
+            KeyCode::Kpd_RBrc => 0xb9,
+            KeyCode::Kpd_Tabz => 0xba,
+            KeyCode::Kpd_Bksp => 0xbb,
+            KeyCode::Kpd_Azzz => 0xbc,
+            KeyCode::Kpd_Bzzz => 0xbd,
+            KeyCode::Kpd_Czzz => 0xbe,
+            KeyCode::Kpd_Dzzz => 0xbf,
+            KeyCode::Kpd_Ezzz => 0xc0,
+            KeyCode::Kpd_Fzzz => 0xc1,
+            KeyCode::Kpd_Xorz => 0xc2,
+            KeyCode::Kpd_Crtz => 0xc3,
+            KeyCode::Kpd_Prct => 0xc4,
+            KeyCode::Kpd_LThn => 0xc5,
+            KeyCode::Kpd_GThn => 0xc6,
+            KeyCode::Kpd_Ampr => 0xc7,
+            KeyCode::Kpd_DAmp => 0xc8,
+            KeyCode::Kpd_Pipe => 0xc9,
+            KeyCode::Kpd_DPip => 0xca,
+            KeyCode::Kpd_Coln => 0xcb,
+            KeyCode::Kpd_Hash => 0xcc,
+            KeyCode::Kpd_Spcz => 0xcd,
+            KeyCode::Kpd_Atzz => 0xce,
+            KeyCode::Kpd_Excl => 0xcf,
+            KeyCode::Kpd_MSto => 0xd0,
+            KeyCode::Kpd_MRcl => 0xd1,
+            KeyCode::Kpd_MClr => 0xd2,
+            KeyCode::Kpd_MAdd => 0xd3,
+            KeyCode::Kpd_MSub => 0xd4,
+            KeyCode::Kpd_MMul => 0xd5,
+            KeyCode::Kpd_MDiv => 0xd6,
+            KeyCode::Kpd_PlMn => 0xd7,
+            KeyCode::Kpd_Clrz => 0xd8,
+            KeyCode::Kpd_ClrE => 0xd9,
+            KeyCode::Kpd_Binz => 0xda,
+            KeyCode::Kpd_Octz => 0xdb,
+            KeyCode::Kpd_Decz => 0xdc,
+            KeyCode::Kpd_Hexz => 0xdd,
+            KeyCode::Fun_Appl => 0x65,
+            KeyCode::Fun_Powr => 0x66,
+            KeyCode::Fun_Exec => 0x74,
+            KeyCode::Fun_Help => 0x75,
+            KeyCode::Fun_Menu => 0x76,
+            KeyCode::Fun_Selz => 0x77,
+            KeyCode::Fun_Stop => 0x78,
+            KeyCode::Fun_Agan => 0x79,
+            KeyCode::Fun_Undo => 0x7a,
+            KeyCode::Fun_Cutz => 0x7b,
+            KeyCode::Fun_Copy => 0x7c,
+            KeyCode::Fun_Pste => 0x7d,
+            KeyCode::Fun_Find => 0x7e,
+            KeyCode::Fun_LCap => 0x82,
+            KeyCode::Fun_LNum => 0x83,
+            KeyCode::Fun_LScr => 0x84,
+            KeyCode::Fun_AErs => 0x99,
+            KeyCode::Fun_SysR => 0x9a,
+            KeyCode::Fun_Cncl => 0x9b,
+            KeyCode::Fun_Clrz => 0x9c,
+            KeyCode::Fun_Prir => 0x9d,
+            KeyCode::Fun_Retn => 0x9e,
+            KeyCode::Fun_Sepr => 0x9f,
+            KeyCode::Fun_Outz => 0xa0,
+            KeyCode::Fun_Oper => 0xa1,
+            KeyCode::Fun_ClrA => 0xa2,
+            KeyCode::Fun_CrSl => 0xa3,
+            KeyCode::Fun_ExSl => 0xa4,
+            KeyCode::Int_1zzz => 0x87,
+            KeyCode::Int_2zzz => 0x88,
+            KeyCode::Int_3zzz => 0x89,
+            KeyCode::Int_4zzz => 0x8a,
+            KeyCode::Int_5zzz => 0x8b,
+            KeyCode::Int_6zzz => 0x8c,
+            KeyCode::Int_7zzz => 0x8d,
+            KeyCode::Int_8zzz => 0x8e,
+            KeyCode::Int_9zzz => 0x8f,
+            KeyCode::Lng_1zzz => 0x90,
+            KeyCode::Lng_2zzz => 0x91,
+            KeyCode::Lng_3zzz => 0x92,
+            KeyCode::Lng_4zzz => 0x93,
+            KeyCode::Lng_5zzz => 0x94,
+            KeyCode::Lng_6zzz => 0x95,
+            KeyCode::Lng_7zzz => 0x96,
+            KeyCode::Lng_8zzz => 0x97,
+            KeyCode::Lng_9zzz => 0x98,
             KeyCode::Mod_L01z => 0xf0,
             KeyCode::Mod_LSft => 0xe1,
             KeyCode::Mod_LCtl => 0xe0,
@@ -418,6 +838,8 @@ impl From<KeyCode> for &str {
             KeyCode::Sym_Coma => "Sym_Coma",
             KeyCode::Sym_Perd => "Sym_Perd",
             KeyCode::Sym_FSla => "Sym_FSla",
+            KeyCode::Sym_NUHs => "Sym_NUHs",
+            KeyCode::Sym_NUBs => "Sym_NUBs",
             KeyCode::Fun_Caps => "Fun_Caps",
             KeyCode::Fun_F1zz => "Fun_F1zz",
             KeyCode::Fun_F2zz => "Fun_F2zz",
@@ -431,10 +853,26 @@ impl From<KeyCode> for &str {
             KeyCode::Fun_F10z => "Fun_F10z",
             KeyCode::Fun_F11z => "Fun_F11z",
             KeyCode::Fun_F12z => "Fun_F12z",
+            KeyCode::Fun_F13z => "Fun_F13z",
+            KeyCode::Fun_F14z => "Fun_F14z",
+            KeyCode::Fun_F15z => "Fun_F15z",
+            KeyCode::Fun_F16z => "Fun_F16z",
+            KeyCode::Fun_F17z => "Fun_F17z",
+            KeyCode::Fun_F18z => "Fun_F18z",
+            KeyCode::Fun_F19z => "Fun_F19z",
+            KeyCode::Fun_F20z => "Fun_F20z",
+            KeyCode::Fun_F21z => "Fun_F21z",
+            KeyCode::Fun_F22z => "Fun_F22z",
+            KeyCode::Fun_F23z => "Fun_F23z",
+            KeyCode::Fun_F24z => "Fun_F24z",
             KeyCode::Arw_Rght => "Arw_Rght",
             KeyCode::Arw_Left => "Arw_Left",
             KeyCode::Arw_Down => "Arw_Down",
             KeyCode::Arw_Upzz => "Arw_Upzz",
+            KeyCode::Fun_PScr => "Fun_PScr",
+            KeyCode::Fun_SLck => "Fun_SLck",
+            KeyCode::Fun_Paus => "Fun_Paus",
+            KeyCode::Fun_Insz => "Fun_Insz",
             KeyCode::Fun_Home => "Fun_Home",
             KeyCode::Fun_PgUp => "Fun_PgUp",
             KeyCode::Fun_Delz => "Fun_Delz",
@@ -443,8 +881,139 @@ impl From<KeyCode> for &str {
             KeyCode::Vol_Mute => "Vol_Mute",
             KeyCode::Vol_Upzz => "Vol_Upzz",
             KeyCode::Vol_Down => "Vol_Down",
+            KeyCode::Sym_Excl => "Sym_Excl",
+            KeyCode::Sym_Atzz => "Sym_Atzz",
+            KeyCode::Sym_Hash => "Sym_Hash",
+            KeyCode::Sym_Dolr => "Sym_Dolr",
+            KeyCode::Sym_Prct => "Sym_Prct",
+            KeyCode::Sym_Crtz => "Sym_Crtz",
+            KeyCode::Sym_Ampr => "Sym_Ampr",
+            KeyCode::Sym_Astr => "Sym_Astr",
             KeyCode::Sym_LPar => "Sym_LPar",
             KeyCode::Sym_RPar => "Sym_RPar",
+            KeyCode::Sym_Unds => "Sym_Unds",
+            KeyCode::Sym_Plus => "Sym_Plus",
+            KeyCode::Sym_LBrc => "Sym_LBrc",
+            KeyCode::Sym_RBrc => "Sym_RBrc",
+            KeyCode::Sym_Pipe => "Sym_Pipe",
+            KeyCode::Sym_Coln => "Sym_Coln",
+            KeyCode::Sym_DQut => "Sym_DQut",
+            KeyCode::Sym_TldS => "Sym_TldS",
+            KeyCode::Sym_LThn => "Sym_LThn",
+            KeyCode::Sym_GThn => "Sym_GThn",
+            KeyCode::Sym_Ques => "Sym_Ques",
+            KeyCode::Fun_NLck => "Fun_NLck",
+            KeyCode::Kpd_Slsh => "Kpd_Slsh",
+            KeyCode::Kpd_Astr => "Kpd_Astr",
+            KeyCode::Kpd_Minz => "Kpd_Minz",
+            KeyCode::Kpd_Plus => "Kpd_Plus",
+            KeyCode::Kpd_Entz => "Kpd_Entz",
+            KeyCode::Kpd_1zzz => "Kpd_1zzz",
+            KeyCode::Kpd_2zzz => "Kpd_2zzz",
+            KeyCode::Kpd_3zzz => "Kpd_3zzz",
+            KeyCode::Kpd_4zzz => "Kpd_4zzz",
+            KeyCode::Kpd_5zzz => "Kpd_5zzz",
+            KeyCode::Kpd_6zzz => "Kpd_6zzz",
+            KeyCode::Kpd_7zzz => "Kpd_7zzz",
+            KeyCode::Kpd_8zzz => "Kpd_8zzz",
+            KeyCode::Kpd_9zzz => "Kpd_9zzz",
+            KeyCode::Kpd_0zzz => "Kpd_0zzz",
+            KeyCode::Kpd_Perd => "Kpd_Perd",
+            KeyCode::Kpd_Equz => "Kpd_Equz",
+            KeyCode::Kpd_Coma => "Kpd_Coma",
+            KeyCode::Kpd_EqAS => "Kpd_EqAS",
+            KeyCode::Kpd_00zz => "Kpd_00zz",
+            KeyCode::Kpd_000z => "Kpd_000z",
+            KeyCode::Kpd_ThSp => "Kpd_ThSp",
+            KeyCode::Kpd_DcSp => "Kpd_DcSp",
+            KeyCode::Kpd_CrUn => "Kpd_CrUn",
+            KeyCode::Kpd_CrSu => "Kpd_CrSu",
+            KeyCode::Kpd_LPar => "Kpd_LPar",
+            KeyCode::Kpd_RPar => "Kpd_RPar",
+            KeyCode::Kpd_LBrc => "Kpd_LBrc",
+            KeyCode::Kpd_RBrc => "Kpd_RBrc",
+            KeyCode::Kpd_Tabz => "Kpd_Tabz",
+            KeyCode::Kpd_Bksp => "Kpd_Bksp",
+            KeyCode::Kpd_Azzz => "Kpd_Azzz",
+            KeyCode::Kpd_Bzzz => "Kpd_Bzzz",
+            KeyCode::Kpd_Czzz => "Kpd_Czzz",
+            KeyCode::Kpd_Dzzz => "Kpd_Dzzz",
+            KeyCode::Kpd_Ezzz => "Kpd_Ezzz",
+            KeyCode::Kpd_Fzzz => "Kpd_Fzzz",
+            KeyCode::Kpd_Xorz => "Kpd_Xorz",
+            KeyCode::Kpd_Crtz => "Kpd_Crtz",
+            KeyCode::Kpd_Prct => "Kpd_Prct",
+            KeyCode::Kpd_LThn => "Kpd_LThn",
+            KeyCode::Kpd_GThn => "Kpd_GThn",
+            KeyCode::Kpd_Ampr => "Kpd_Ampr",
+            KeyCode::Kpd_DAmp => "Kpd_DAmp",
+            KeyCode::Kpd_Pipe => "Kpd_Pipe",
+            KeyCode::Kpd_DPip => "Kpd_DPip",
+            KeyCode::Kpd_Coln => "Kpd_Coln",
+            KeyCode::Kpd_Hash => "Kpd_Hash",
+            KeyCode::Kpd_Spcz => "Kpd_Spcz",
+            KeyCode::Kpd_Atzz => "Kpd_Atzz",
+            KeyCode::Kpd_Excl => "Kpd_Excl",
+            KeyCode::Kpd_MSto => "Kpd_MSto",
+            KeyCode::Kpd_MRcl => "Kpd_MRcl",
+            KeyCode::Kpd_MClr => "Kpd_MClr",
+            KeyCode::Kpd_MAdd => "Kpd_MAdd",
+            KeyCode::Kpd_MSub => "Kpd_MSub",
+            KeyCode::Kpd_MMul => "Kpd_MMul",
+            KeyCode::Kpd_MDiv => "Kpd_MDiv",
+            KeyCode::Kpd_PlMn => "Kpd_PlMn",
+            KeyCode::Kpd_Clrz => "Kpd_Clrz",
+            KeyCode::Kpd_ClrE => "Kpd_ClrE",
+            KeyCode::Kpd_Binz => "Kpd_Binz",
+            KeyCode::Kpd_Octz => "Kpd_Octz",
+            KeyCode::Kpd_Decz => "Kpd_Decz",
+            KeyCode::Kpd_Hexz => "Kpd_Hexz",
+            KeyCode::Fun_Appl => "Fun_Appl",
+            KeyCode::Fun_Powr => "Fun_Powr",
+            KeyCode::Fun_Exec => "Fun_Exec",
+            KeyCode::Fun_Help => "Fun_Help",
+            KeyCode::Fun_Menu => "Fun_Menu",
+            KeyCode::Fun_Selz => "Fun_Selz",
+            KeyCode::Fun_Stop => "Fun_Stop",
+            KeyCode::Fun_Agan => "Fun_Agan",
+            KeyCode::Fun_Undo => "Fun_Undo",
+            KeyCode::Fun_Cutz => "Fun_Cutz",
+            KeyCode::Fun_Copy => "Fun_Copy",
+            KeyCode::Fun_Pste => "Fun_Pste",
+            KeyCode::Fun_Find => "Fun_Find",
+            KeyCode::Fun_LCap => "Fun_LCap",
+            KeyCode::Fun_LNum => "Fun_LNum",
+            KeyCode::Fun_LScr => "Fun_LScr",
+            KeyCode::Fun_AErs => "Fun_AErs",
+            KeyCode::Fun_SysR => "Fun_SysR",
+            KeyCode::Fun_Cncl => "Fun_Cncl",
+            KeyCode::Fun_Clrz => "Fun_Clrz",
+            KeyCode::Fun_Prir => "Fun_Prir",
+            KeyCode::Fun_Retn => "Fun_Retn",
+            KeyCode::Fun_Sepr => "Fun_Sepr",
+            KeyCode::Fun_Outz => "Fun_Outz",
+            KeyCode::Fun_Oper => "Fun_Oper",
+            KeyCode::Fun_ClrA => "Fun_ClrA",
+            KeyCode::Fun_CrSl => "Fun_CrSl",
+            KeyCode::Fun_ExSl => "Fun_ExSl",
+            KeyCode::Int_1zzz => "Int_1zzz",
+            KeyCode::Int_2zzz => "Int_2zzz",
+            KeyCode::Int_3zzz => "Int_3zzz",
+            KeyCode::Int_4zzz => "Int_4zzz",
+            KeyCode::Int_5zzz => "Int_5zzz",
+            KeyCode::Int_6zzz => "Int_6zzz",
+            KeyCode::Int_7zzz => "Int_7zzz",
+            KeyCode::Int_8zzz => "Int_8zzz",
+            KeyCode::Int_9zzz => "Int_9zzz",
+            KeyCode::Lng_1zzz => "Lng_1zzz",
+            KeyCode::Lng_2zzz => "Lng_2zzz",
+            KeyCode::Lng_3zzz => "Lng_3zzz",
+            KeyCode::Lng_4zzz => "Lng_4zzz",
+            KeyCode::Lng_5zzz => "Lng_5zzz",
+            KeyCode::Lng_6zzz => "Lng_6zzz",
+            KeyCode::Lng_7zzz => "Lng_7zzz",
+            KeyCode::Lng_8zzz => "Lng_8zzz",
+            KeyCode::Lng_9zzz => "Lng_9zzz",
             KeyCode::Mod_L01z => "Mod_L01z",
             KeyCode::Mod_LSft => "Mod_LSft",
             KeyCode::Mod_LCtl => "Mod_LCtl",
@@ -456,6 +1025,21 @@ impl From<KeyCode> for &str {
             KeyCode::Mod_RSft => "Mod_RSft",
             KeyCode::Led_Col1 => "Led_Col1",
             KeyCode::Led_Col2 => "Led_Col2",
+            KeyCode::Led_Next => "Led_Next",
+            KeyCode::Led_Prev => "Led_Prev",
+            KeyCode::Led_SpUp => "Led_SpUp",
+            KeyCode::Led_SpDn => "Led_SpDn",
+            KeyCode::Led_BrUp => "Led_BrUp",
+            KeyCode::Led_BrDn => "Led_BrDn",
+            KeyCode::Led_HueU => "Led_HueU",
+            KeyCode::Led_HueD => "Led_HueD",
+            KeyCode::Led_SatU => "Led_SatU",
+            KeyCode::Led_SatD => "Led_SatD",
+            KeyCode::Led_ValU => "Led_ValU",
+            KeyCode::Led_ValD => "Led_ValD",
+            KeyCode::Lyr_Mo1z => "Lyr_Mo1z",
+            KeyCode::Lyr_Next => "Lyr_Next",
+            KeyCode::Fun_Boot => "Fun_Boot",
             KeyCode::________ => "________",
             KeyCode::EEEEEEEE => "EEEEEEEE",
         }
@@ -517,6 +1101,8 @@ impl From<&str> for KeyCode {
             "Sym_Coma" => KeyCode::Sym_Coma,
             "Sym_Perd" => KeyCode::Sym_Perd,
             "Sym_FSla" => KeyCode::Sym_FSla,
+            "Sym_NUHs" => KeyCode::Sym_NUHs,
+            "Sym_NUBs" => KeyCode::Sym_NUBs,
             "Fun_Caps" => KeyCode::Fun_Caps,
             "Fun_F1zz" => KeyCode::Fun_F1zz,
             "Fun_F2zz" => KeyCode::Fun_F2zz,
@@ -530,10 +1116,26 @@ impl From<&str> for KeyCode {
             "Fun_F10z" => KeyCode::Fun_F10z,
             "Fun_F11z" => KeyCode::Fun_F11z,
             "Fun_F12z" => KeyCode::Fun_F12z,
+            "Fun_F13z" => KeyCode::Fun_F13z,
+            "Fun_F14z" => KeyCode::Fun_F14z,
+            "Fun_F15z" => KeyCode::Fun_F15z,
+            "Fun_F16z" => KeyCode::Fun_F16z,
+            "Fun_F17z" => KeyCode::Fun_F17z,
+            "Fun_F18z" => KeyCode::Fun_F18z,
+            "Fun_F19z" => KeyCode::Fun_F19z,
+            "Fun_F20z" => KeyCode::Fun_F20z,
+            "Fun_F21z" => KeyCode::Fun_F21z,
+            "Fun_F22z" => KeyCode::Fun_F22z,
+            "Fun_F23z" => KeyCode::Fun_F23z,
+            "Fun_F24z" => KeyCode::Fun_F24z,
             "Arw_Rght" => KeyCode::Arw_Rght,
             "Arw_Left" => KeyCode::Arw_Left,
             "Arw_Down" => KeyCode::Arw_Down,
             "Arw_Upzz" => KeyCode::Arw_Upzz,
+            "Fun_PScr" => KeyCode::Fun_PScr,
+            "Fun_SLck" => KeyCode::Fun_SLck,
+            "Fun_Paus" => KeyCode::Fun_Paus,
+            "Fun_Insz" => KeyCode::Fun_Insz,
             "Fun_Home" => KeyCode::Fun_Home,
             "Fun_PgUp" => KeyCode::Fun_PgUp,
             "Fun_Delz" => KeyCode::Fun_Delz,
@@ -542,8 +1144,139 @@ impl From<&str> for KeyCode {
             "Vol_Mute" => KeyCode::Vol_Mute,
             "Vol_Upzz" => KeyCode::Vol_Upzz,
             "Vol_Down" => KeyCode::Vol_Down,
+            "Sym_Excl" => KeyCode::Sym_Excl,
+            "Sym_Atzz" => KeyCode::Sym_Atzz,
+            "Sym_Hash" => KeyCode::Sym_Hash,
+            "Sym_Dolr" => KeyCode::Sym_Dolr,
+            "Sym_Prct" => KeyCode::Sym_Prct,
+            "Sym_Crtz" => KeyCode::Sym_Crtz,
+            "Sym_Ampr" => KeyCode::Sym_Ampr,
+            "Sym_Astr" => KeyCode::Sym_Astr,
             "Sym_LPar" => KeyCode::Sym_LPar,
             "Sym_RPar" => KeyCode::Sym_RPar,
+            "Sym_Unds" => KeyCode::Sym_Unds,
+            "Sym_Plus" => KeyCode::Sym_Plus,
+            "Sym_LBrc" => KeyCode::Sym_LBrc,
+            "Sym_RBrc" => KeyCode::Sym_RBrc,
+            "Sym_Pipe" => KeyCode::Sym_Pipe,
+            "Sym_Coln" => KeyCode::Sym_Coln,
+            "Sym_DQut" => KeyCode::Sym_DQut,
+            "Sym_TldS" => KeyCode::Sym_TldS,
+            "Sym_LThn" => KeyCode::Sym_LThn,
+            "Sym_GThn" => KeyCode::Sym_GThn,
+            "Sym_Ques" => KeyCode::Sym_Ques,
+            "Fun_NLck" => KeyCode::Fun_NLck,
+            "Kpd_Slsh" => KeyCode::Kpd_Slsh,
+            "Kpd_Astr" => KeyCode::Kpd_Astr,
+            "Kpd_Minz" => KeyCode::Kpd_Minz,
+            "Kpd_Plus" => KeyCode::Kpd_Plus,
+            "Kpd_Entz" => KeyCode::Kpd_Entz,
+            "Kpd_1zzz" => KeyCode::Kpd_1zzz,
+            "Kpd_2zzz" => KeyCode::Kpd_2zzz,
+            "Kpd_3zzz" => KeyCode::Kpd_3zzz,
+            "Kpd_4zzz" => KeyCode::Kpd_4zzz,
+            "Kpd_5zzz" => KeyCode::Kpd_5zzz,
+            "Kpd_6zzz" => KeyCode::Kpd_6zzz,
+            "Kpd_7zzz" => KeyCode::Kpd_7zzz,
+            "Kpd_8zzz" => KeyCode::Kpd_8zzz,
+            "Kpd_9zzz" => KeyCode::Kpd_9zzz,
+            "Kpd_0zzz" => KeyCode::Kpd_0zzz,
+            "Kpd_Perd" => KeyCode::Kpd_Perd,
+            "Kpd_Equz" => KeyCode::Kpd_Equz,
+            "Kpd_Coma" => KeyCode::Kpd_Coma,
+            "Kpd_EqAS" => KeyCode::Kpd_EqAS,
+            "Kpd_00zz" => KeyCode::Kpd_00zz,
+            "Kpd_000z" => KeyCode::Kpd_000z,
+            "Kpd_ThSp" => KeyCode::Kpd_ThSp,
+            "Kpd_DcSp" => KeyCode::Kpd_DcSp,
+            "Kpd_CrUn" => KeyCode::Kpd_CrUn,
+            "Kpd_CrSu" => KeyCode::Kpd_CrSu,
+            "Kpd_LPar" => KeyCode::Kpd_LPar,
+            "Kpd_RPar" => KeyCode::Kpd_RPar,
+            "Kpd_LBrc" => KeyCode::Kpd_LBrc,
+            "Kpd_RBrc" => KeyCode::Kpd_RBrc,
+            "Kpd_Tabz" => KeyCode::Kpd_Tabz,
+            "Kpd_Bksp" => KeyCode::Kpd_Bksp,
+            "Kpd_Azzz" => KeyCode::Kpd_Azzz,
+            "Kpd_Bzzz" => KeyCode::Kpd_Bzzz,
+            "Kpd_Czzz" => KeyCode::Kpd_Czzz,
+            "Kpd_Dzzz" => KeyCode::Kpd_Dzzz,
+            "Kpd_Ezzz" => KeyCode::Kpd_Ezzz,
+            "Kpd_Fzzz" => KeyCode::Kpd_Fzzz,
+            "Kpd_Xorz" => KeyCode::Kpd_Xorz,
+            "Kpd_Crtz" => KeyCode::Kpd_Crtz,
+            "Kpd_Prct" => KeyCode::Kpd_Prct,
+            "Kpd_LThn" => KeyCode::Kpd_LThn,
+            "Kpd_GThn" => KeyCode::Kpd_GThn,
+            "Kpd_Ampr" => KeyCode::Kpd_Ampr,
+            "Kpd_DAmp" => KeyCode::Kpd_DAmp,
+            "Kpd_Pipe" => KeyCode::Kpd_Pipe,
+            "Kpd_DPip" => KeyCode::Kpd_DPip,
+            "Kpd_Coln" => KeyCode::Kpd_Coln,
+            "Kpd_Hash" => KeyCode::Kpd_Hash,
+            "Kpd_Spcz" => KeyCode::Kpd_Spcz,
+            "Kpd_Atzz" => KeyCode::Kpd_Atzz,
+            "Kpd_Excl" => KeyCode::Kpd_Excl,
+            "Kpd_MSto" => KeyCode::Kpd_MSto,
+            "Kpd_MRcl" => KeyCode::Kpd_MRcl,
+            "Kpd_MClr" => KeyCode::Kpd_MClr,
+            "Kpd_MAdd" => KeyCode::Kpd_MAdd,
+            "Kpd_MSub" => KeyCode::Kpd_MSub,
+            "Kpd_MMul" => KeyCode::Kpd_MMul,
+            "Kpd_MDiv" => KeyCode::Kpd_MDiv,
+            "Kpd_PlMn" => KeyCode::Kpd_PlMn,
+            "Kpd_Clrz" => KeyCode::Kpd_Clrz,
+            "Kpd_ClrE" => KeyCode::Kpd_ClrE,
+            "Kpd_Binz" => KeyCode::Kpd_Binz,
+            "Kpd_Octz" => KeyCode::Kpd_Octz,
+            "Kpd_Decz" => KeyCode::Kpd_Decz,
+            "Kpd_Hexz" => KeyCode::Kpd_Hexz,
+            "Fun_Appl" => KeyCode::Fun_Appl,
+            "Fun_Powr" => KeyCode::Fun_Powr,
+            "Fun_Exec" => KeyCode::Fun_Exec,
+            "Fun_Help" => KeyCode::Fun_Help,
+            "Fun_Menu" => KeyCode::Fun_Menu,
+            "Fun_Selz" => KeyCode::Fun_Selz,
+            "Fun_Stop" => KeyCode::Fun_Stop,
+            "Fun_Agan" => KeyCode::Fun_Agan,
+            "Fun_Undo" => KeyCode::Fun_Undo,
+            "Fun_Cutz" => KeyCode::Fun_Cutz,
+            "Fun_Copy" => KeyCode::Fun_Copy,
+            "Fun_Pste" => KeyCode::Fun_Pste,
+            "Fun_Find" => KeyCode::Fun_Find,
+            "Fun_LCap" => KeyCode::Fun_LCap,
+            "Fun_LNum" => KeyCode::Fun_LNum,
+            "Fun_LScr" => KeyCode::Fun_LScr,
+            "Fun_AErs" => KeyCode::Fun_AErs,
+            "Fun_SysR" => KeyCode::Fun_SysR,
+            "Fun_Cncl" => KeyCode::Fun_Cncl,
+            "Fun_Clrz" => KeyCode::Fun_Clrz,
+            "Fun_Prir" => KeyCode::Fun_Prir,
+            "Fun_Retn" => KeyCode::Fun_Retn,
+            "Fun_Sepr" => KeyCode::Fun_Sepr,
+            "Fun_Outz" => KeyCode::Fun_Outz,
+            "Fun_Oper" => KeyCode::Fun_Oper,
+            "Fun_ClrA" => KeyCode::Fun_ClrA,
+            "Fun_CrSl" => KeyCode::Fun_CrSl,
+            "Fun_ExSl" => KeyCode::Fun_ExSl,
+            "Int_1zzz" => KeyCode::Int_1zzz,
+            "Int_2zzz" => KeyCode::Int_2zzz,
+            "Int_3zzz" => KeyCode::Int_3zzz,
+            "Int_4zzz" => KeyCode::Int_4zzz,
+            "Int_5zzz" => KeyCode::Int_5zzz,
+            "Int_6zzz" => KeyCode::Int_6zzz,
+            "Int_7zzz" => KeyCode::Int_7zzz,
+            "Int_8zzz" => KeyCode::Int_8zzz,
+            "Int_9zzz" => KeyCode::Int_9zzz,
+            "Lng_1zzz" => KeyCode::Lng_1zzz,
+            "Lng_2zzz" => KeyCode::Lng_2zzz,
+            "Lng_3zzz" => KeyCode::Lng_3zzz,
+            "Lng_4zzz" => KeyCode::Lng_4zzz,
+            "Lng_5zzz" => KeyCode::Lng_5zzz,
+            "Lng_6zzz" => KeyCode::Lng_6zzz,
+            "Lng_7zzz" => KeyCode::Lng_7zzz,
+            "Lng_8zzz" => KeyCode::Lng_8zzz,
+            "Lng_9zzz" => KeyCode::Lng_9zzz,
             "Mod_L01z" => KeyCode::Mod_L01z,
             "Mod_LSft" => KeyCode::Mod_LSft,
             "Mod_LCtl" => KeyCode::Mod_LCtl,
@@ -555,9 +1288,130 @@ impl From<&str> for KeyCode {
             "Mod_RSft" => KeyCode::Mod_RSft,
             "Led_Col1" => KeyCode::Led_Col1,
             "Led_Col2" => KeyCode::Led_Col2,
+            "Led_Next" => KeyCode::Led_Next,
+            "Led_Prev" => KeyCode::Led_Prev,
+            "Led_SpUp" => KeyCode::Led_SpUp,
+            "Led_SpDn" => KeyCode::Led_SpDn,
+            "Led_BrUp" => KeyCode::Led_BrUp,
+            "Led_BrDn" => KeyCode::Led_BrDn,
+            "Led_HueU" => KeyCode::Led_HueU,
+            "Led_HueD" => KeyCode::Led_HueD,
+            "Led_SatU" => KeyCode::Led_SatU,
+            "Led_SatD" => KeyCode::Led_SatD,
+            "Led_ValU" => KeyCode::Led_ValU,
+            "Led_ValD" => KeyCode::Led_ValD,
+            "Lyr_Mo1z" => KeyCode::Lyr_Mo1z,
+            "Lyr_Next" => KeyCode::Lyr_Next,
+            "Fun_Boot" => KeyCode::Fun_Boot,
             "________" => KeyCode::________,
             "EEEEEEEE" => KeyCode::EEEEEEEE,
             _ => KeyCode::EEEEEEEE,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::KeyCode;
+
+    /// every keycode in declaration order, a new variant has to be added here too
+    #[rustfmt::skip]
+    const ALL: [KeyCode; 256] = [
+        KeyCode::EEEEEEEE, KeyCode::________, KeyCode::Ltr_Azzz, KeyCode::Ltr_Bzzz,
+        KeyCode::Ltr_Czzz, KeyCode::Ltr_Dzzz, KeyCode::Ltr_Ezzz, KeyCode::Ltr_Fzzz,
+        KeyCode::Ltr_Gzzz, KeyCode::Ltr_Hzzz, KeyCode::Ltr_Izzz, KeyCode::Ltr_Jzzz,
+        KeyCode::Ltr_Kzzz, KeyCode::Ltr_Lzzz, KeyCode::Ltr_Mzzz, KeyCode::Ltr_Nzzz,
+        KeyCode::Ltr_Ozzz, KeyCode::Ltr_Pzzz, KeyCode::Ltr_Qzzz, KeyCode::Ltr_Rzzz,
+        KeyCode::Ltr_Szzz, KeyCode::Ltr_Tzzz, KeyCode::Ltr_Uzzz, KeyCode::Ltr_Vzzz,
+        KeyCode::Ltr_Wzzz, KeyCode::Ltr_Xzzz, KeyCode::Ltr_Yzzz, KeyCode::Ltr_Zzzz,
+        KeyCode::Num_1zzz, KeyCode::Num_2zzz, KeyCode::Num_3zzz, KeyCode::Num_4zzz,
+        KeyCode::Num_5zzz, KeyCode::Num_6zzz, KeyCode::Num_7zzz, KeyCode::Num_8zzz,
+        KeyCode::Num_9zzz, KeyCode::Num_0zzz, KeyCode::Fun_Entz, KeyCode::Fun_Escz,
+        KeyCode::Fun_Bksp, KeyCode::Fun_Tabz, KeyCode::Fun_Spcz, KeyCode::Sym_Minz,
+        KeyCode::Sym_Equz, KeyCode::Sym_LBrk, KeyCode::Sym_RBrk, KeyCode::Sym_BSla,
+        KeyCode::Sym_Scln, KeyCode::Sym_SQut, KeyCode::Sym_Tild, KeyCode::Sym_Coma,
+        KeyCode::Sym_Perd, KeyCode::Sym_FSla, KeyCode::Sym_NUHs, KeyCode::Sym_NUBs,
+        KeyCode::Fun_Caps, KeyCode::Fun_F1zz, KeyCode::Fun_F2zz, KeyCode::Fun_F3zz,
+        KeyCode::Fun_F4zz, KeyCode::Fun_F5zz, KeyCode::Fun_F6zz, KeyCode::Fun_F7zz,
+        KeyCode::Fun_F8zz, KeyCode::Fun_F9zz, KeyCode::Fun_F10z, KeyCode::Fun_F11z,
+        KeyCode::Fun_F12z, KeyCode::Fun_F13z, KeyCode::Fun_F14z, KeyCode::Fun_F15z,
+        KeyCode::Fun_F16z, KeyCode::Fun_F17z, KeyCode::Fun_F18z, KeyCode::Fun_F19z,
+        KeyCode::Fun_F20z, KeyCode::Fun_F21z, KeyCode::Fun_F22z, KeyCode::Fun_F23z,
+        KeyCode::Fun_F24z, KeyCode::Arw_Rght, KeyCode::Arw_Left, KeyCode::Arw_Down,
+        KeyCode::Arw_Upzz, KeyCode::Fun_PScr, KeyCode::Fun_SLck, KeyCode::Fun_Paus,
+        KeyCode::Fun_Insz, KeyCode::Fun_Home, KeyCode::Fun_PgUp, KeyCode::Fun_Delz,
+        KeyCode::Fun_Endz, KeyCode::Fun_PgDn, KeyCode::Vol_Mute, KeyCode::Vol_Upzz,
+        KeyCode::Vol_Down, KeyCode::Sym_Excl, KeyCode::Sym_Atzz, KeyCode::Sym_Hash,
+        KeyCode::Sym_Dolr, KeyCode::Sym_Prct, KeyCode::Sym_Crtz, KeyCode::Sym_Ampr,
+        KeyCode::Sym_Astr, KeyCode::Sym_LPar, KeyCode::Sym_RPar, KeyCode::Sym_Unds,
+        KeyCode::Sym_Plus, KeyCode::Sym_LBrc, KeyCode::Sym_RBrc, KeyCode::Sym_Pipe,
+        KeyCode::Sym_Coln, KeyCode::Sym_DQut, KeyCode::Sym_TldS, KeyCode::Sym_LThn,
+        KeyCode::Sym_GThn, KeyCode::Sym_Ques, KeyCode::Fun_NLck, KeyCode::Kpd_Slsh,
+        KeyCode::Kpd_Astr, KeyCode::Kpd_Minz, KeyCode::Kpd_Plus, KeyCode::Kpd_Entz,
+        KeyCode::Kpd_1zzz, KeyCode::Kpd_2zzz, KeyCode::Kpd_3zzz, KeyCode::Kpd_4zzz,
+        KeyCode::Kpd_5zzz, KeyCode::Kpd_6zzz, KeyCode::Kpd_7zzz, KeyCode::Kpd_8zzz,
+        KeyCode::Kpd_9zzz, KeyCode::Kpd_0zzz, KeyCode::Kpd_Perd, KeyCode::Kpd_Equz,
+        KeyCode::Kpd_Coma, KeyCode::Kpd_EqAS, KeyCode::Kpd_00zz, KeyCode::Kpd_000z,
+        KeyCode::Kpd_ThSp, KeyCode::Kpd_DcSp, KeyCode::Kpd_CrUn, KeyCode::Kpd_CrSu,
+        KeyCode::Kpd_LPar, KeyCode::Kpd_RPar, KeyCode::Kpd_LBrc, KeyCode::Kpd_RBrc,
+        KeyCode::Kpd_Tabz, KeyCode::Kpd_Bksp, KeyCode::Kpd_Azzz, KeyCode::Kpd_Bzzz,
+        KeyCode::Kpd_Czzz, KeyCode::Kpd_Dzzz, KeyCode::Kpd_Ezzz, KeyCode::Kpd_Fzzz,
+        KeyCode::Kpd_Xorz, KeyCode::Kpd_Crtz, KeyCode::Kpd_Prct, KeyCode::Kpd_LThn,
+        KeyCode::Kpd_GThn, KeyCode::Kpd_Ampr, KeyCode::Kpd_DAmp, KeyCode::Kpd_Pipe,
+        KeyCode::Kpd_DPip, KeyCode::Kpd_Coln, KeyCode::Kpd_Hash, KeyCode::Kpd_Spcz,
+        KeyCode::Kpd_Atzz, KeyCode::Kpd_Excl, KeyCode::Kpd_MSto, KeyCode::Kpd_MRcl,
+        KeyCode::Kpd_MClr, KeyCode::Kpd_MAdd, KeyCode::Kpd_MSub, KeyCode::Kpd_MMul,
+        KeyCode::Kpd_MDiv, KeyCode::Kpd_PlMn, KeyCode::Kpd_Clrz, KeyCode::Kpd_ClrE,
+        KeyCode::Kpd_Binz, KeyCode::Kpd_Octz, KeyCode::Kpd_Decz, KeyCode::Kpd_Hexz,
+        KeyCode::Fun_Appl, KeyCode::Fun_Powr, KeyCode::Fun_Exec, KeyCode::Fun_Help,
+        KeyCode::Fun_Menu, KeyCode::Fun_Selz, KeyCode::Fun_Stop, KeyCode::Fun_Agan,
+        KeyCode::Fun_Undo, KeyCode::Fun_Cutz, KeyCode::Fun_Copy, KeyCode::Fun_Pste,
+        KeyCode::Fun_Find, KeyCode::Fun_LCap, KeyCode::Fun_LNum, KeyCode::Fun_LScr,
+        KeyCode::Fun_AErs, KeyCode::Fun_SysR, KeyCode::Fun_Cncl, KeyCode::Fun_Clrz,
+        KeyCode::Fun_Prir, KeyCode::Fun_Retn, KeyCode::Fun_Sepr, KeyCode::Fun_Outz,
+        KeyCode::Fun_Oper, KeyCode::Fun_ClrA, KeyCode::Fun_CrSl, KeyCode::Fun_ExSl,
+        KeyCode::Int_1zzz, KeyCode::Int_2zzz, KeyCode::Int_3zzz, KeyCode::Int_4zzz,
+        KeyCode::Int_5zzz, KeyCode::Int_6zzz, KeyCode::Int_7zzz, KeyCode::Int_8zzz,
+        KeyCode::Int_9zzz, KeyCode::Lng_1zzz, KeyCode::Lng_2zzz, KeyCode::Lng_3zzz,
+        KeyCode::Lng_4zzz, KeyCode::Lng_5zzz, KeyCode::Lng_6zzz, KeyCode::Lng_7zzz,
+        KeyCode::Lng_8zzz, KeyCode::Lng_9zzz, KeyCode::Mod_L01z, KeyCode::Mod_LSft,
+        KeyCode::Mod_LCtl, KeyCode::Mod_LAlt, KeyCode::Mod_LCmd, KeyCode::Mod_RCmd,
+        KeyCode::Mod_RAlt, KeyCode::Mod_RCtl, KeyCode::Mod_RSft, KeyCode::Led_Col1,
+        KeyCode::Led_Col2, KeyCode::Led_Next, KeyCode::Led_Prev, KeyCode::Led_SpUp,
+        KeyCode::Led_SpDn, KeyCode::Led_BrUp, KeyCode::Led_BrDn, KeyCode::Led_HueU,
+        KeyCode::Led_HueD, KeyCode::Led_SatU, KeyCode::Led_SatD, KeyCode::Led_ValU,
+        KeyCode::Led_ValD, KeyCode::Lyr_Mo1z, KeyCode::Lyr_Next, KeyCode::Fun_Boot,
+    ];
+
+    #[test]
+    fn table_is_in_declaration_order() {
+        for (i, code) in ALL.iter().enumerate() {
+            assert_eq!(*code as usize, i, "{:?} is out of place", code);
+        }
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for code in ALL {
+            let name: &str = code.into();
+            assert_eq!(name.len(), 8, "{:?} has name {}", code, name);
+            assert_eq!(KeyCode::from(name), code, "{} doesn't parse back", name);
+        }
+    }
+
+    #[test]
+    fn only_firmware_keys_have_no_hid_usage() {
+        for code in ALL {
+            let usage: u8 = code.into();
+            let firmware_only =
+                code.is_firmware() || matches!(code, KeyCode::EEEEEEEE | KeyCode::________);
+            assert_eq!(
+                usage == 0x00,
+                firmware_only,
+                "{:?} has HID usage {:#x}",
+                code,
+                usage
+            );
+        }
+    }
+}
@@ -1,10 +1,12 @@
-use heapless::Vec;
+use heapless::{String, Vec};
 
 use crate::key::Key;
 use crate::key_codes::KeyCode;
 use crate::mods::mod_combo::ModCombo;
 use crate::mods::mod_tapcom::TapCom;
 use crate::mods::rgb_key::RGBKey;
+#[cfg(feature = "encoders")]
+use crate::{board, layers};
 use crate::{key::Default, keyscanning::KeyMatrix, mods::mod_tap::ModTap};
 
 #[rustfmt::skip]
@@ -38,8 +40,138 @@ pub const ERGOONE_1: [&str; 80] = [
 "df,________", "df,________","df,________","df,________","df,________","df,________","df,EEEEEEEE","df,EEEEEEEE","df,EEEEEEEE","df,EEEEEEEE","df,EEEEEEEE","df,________","df,________","df,________","df,________","df,________",
 ];
 
+/// Keys tapped by each encoder step as [clockwise, counter-clockwise], one set per layer
+/// Takes the same entries as the keymap
+#[cfg(feature = "encoders")]
+#[rustfmt::skip]
+pub const ENCODER_MAP: [[[&str; 2]; board::ENCODERS]; layers::LAYERS] = [
+    [["df,Vol_Upzz", "df,Vol_Down"]],
+    [["df,Arw_Down", "df,Arw_Upzz"]],
+    [["df,Led_HueU", "df,Led_HueD"]],
+];
+
+/// parse ENCODER_MAP or one like it
+#[cfg(feature = "encoders")]
+pub fn encoder_keys<const N: usize, const L: usize>(
+    map: [[[&str; 2]; N]; L],
+) -> [[[Key; 2]; N]; L] {
+    map.map(|layer| layer.map(|dirs| dirs.map(str_to_key)))
+}
+
 // TODO use enum or lookup function to get the parsing function for these key strings from the
 // modules themselves instead of writing the parsing functions here
+/// parse a single keymap entry(e.g. "mt,Fun_Escz,Mod_LCtl") into a Key
+pub fn str_to_key(sel: &str) -> Key {
+    // TODO use split and join with trim to remove whitespace instead of slicing the
+    // string and then parsing it
+    if sel.starts_with("df,") {
+        let b: usize = sel.find("df,").unwrap() + 3;
+        Default::new(sel[b..].into(), None)
+    } else if sel.starts_with("mt,") {
+        let b: usize = sel.find("mt,").unwrap_or(0) + 3;
+        match fields::<2>(&sel[b..], ',') {
+            Some(sr) if sr.len() == 2 => ModTap::mtnew(sr[0].into(), sr[1].into()),
+            _ => Default::new("EEEEEEEE".into(), None),
+        }
+    } else if sel.starts_with("tc,") {
+        let b: usize = sel.find("tc,").unwrap_or(0) + 3;
+        match fields::<3>(&sel[b..], ',') {
+            Some(sr) if sr.len() == 3 => TapCom::tcnew(sr[0].into(), (sr[1].into(), sr[2].into())),
+            _ => Default::new("EEEEEEEE".into(), None),
+        }
+    } else if sel.starts_with("mc,") {
+        let b: usize = sel.find("mc,").unwrap_or(0) + 3;
+        match fields::<2>(&sel[b..], ',') {
+            Some(sr) if sr.len() == 2 => ModCombo::mcnew(sr[0].into(), sr[1].into()),
+            _ => Default::new("EEEEEEEE".into(), None),
+        }
+    } else if sel.starts_with("rk,") {
+        let b: usize = sel.find("rk,").unwrap_or(0) + 3;
+        // "rk,r_g_b" colors the whole strip, "rk,r_g_b,row_col" the LED under that key
+        let Some(parts) = fields::<2>(&sel[b..], ',') else {
+            return Default::new("EEEEEEEE".into(), None);
+        };
+        let sr = fields::<3>(parts[0], '_').unwrap_or_default();
+        let target = match parts.get(1) {
+            Some(t) => {
+                let rc = fields::<2>(t, '_').unwrap_or_default();
+                match (
+                    rc.first().and_then(|x| x.parse().ok()),
+                    rc.get(1).and_then(|x| x.parse().ok()),
+                ) {
+                    (Some(row), Some(col)) => Some((row, col)),
+                    _ => return Default::new("EEEEEEEE".into(), None),
+                }
+            }
+            None => None,
+        };
+        match (
+            sr.first().and_then(|x| x.parse().ok()),
+            sr.get(1).and_then(|x| x.parse().ok()),
+            sr.get(2).and_then(|x| x.parse().ok()),
+        ) {
+            (Some(r), Some(g), Some(b)) if sr.len() == 3 => RGBKey::rknew(r, g, b, target),
+            _ => Default::new("EEEEEEEE".into(), None),
+        }
+    } else {
+        Default::new("EEEEEEEE".into(), None)
+    }
+}
+
+/// the trimmed fields of s split on sep, None if there are more than N
+/// entries come from the host through setkey so they can be anything
+fn fields<const N: usize>(s: &str, sep: char) -> Option<Vec<&str, N>> {
+    let mut out = Vec::new();
+    for field in s.split(sep) {
+        out.push(field.trim()).ok()?;
+    }
+    Some(out)
+}
+
+/// turn a Key back into the keymap entry that would create it
+pub fn key_to_str(key: &Key) -> String<40> {
+    let mut s: String<40> = String::new();
+    let code = |i: usize| -> &str {
+        key.keycode[i]
+            .map(|k| k.0)
+            .unwrap_or(KeyCode::EEEEEEEE)
+            .into()
+    };
+    let mut push = |parts: &[&str]| {
+        for (i, p) in parts.iter().enumerate() {
+            if i > 0 {
+                s.push_str(",").ok();
+            }
+            s.push_str(p).ok();
+        }
+    };
+    match key.typ {
+        "Default" => push(&["df", code(0)]),
+        "ModTap" => push(&["mt", code(0), code(1)]),
+        "TapCom" => push(&["tc", code(0), code(1), code(2)]),
+        "ModCombo" => push(&["mc", code(0), code(1)]),
+        "RGBKey" => {
+            let join = |vals: &[u8]| {
+                let mut out: String<11> = String::new();
+                for (i, c) in vals.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str("_").ok();
+                    }
+                    out.push_str(&String::<3>::from(*c)).ok();
+                }
+                out
+            };
+            if key.stor[3] != 0 {
+                push(&["rk", &join(&key.stor[0..3]), &join(&key.stor[4..6])])
+            } else {
+                push(&["rk", &join(&key.stor[0..3])])
+            }
+        }
+        _ => push(&["df", "EEEEEEEE"]),
+    }
+    s
+}
+
 impl<const RSIZE: usize, const CSIZE: usize> From<[&str; RSIZE * CSIZE]>
     for KeyMatrix<RSIZE, CSIZE>
 {
@@ -53,39 +185,7 @@ impl<const RSIZE: usize, const CSIZE: usize> From<[&str; RSIZE * CSIZE]>
                 c = 0;
             }
             if sel.len() > 0 {
-                // TODO use split and join with trim to remove whitespace instead of slicing the
-                // string and then parsing it
-                if sel.starts_with("df,") {
-                    let b: usize = sel.find("df,").unwrap() + 3;
-                    m[r][c] = Default::new(sel[b..].into(), None);
-                } else if sel.starts_with("mt,") {
-                    let b: usize = sel.find("mt,").unwrap_or(0) + 3;
-                    let sr = sel[b..]
-                        .split(",")
-                        .map(|s| s.trim())
-                        .collect::<Vec<&str, 2>>();
-                    m[r][c] = ModTap::mtnew(sr[0].into(), sr[1].into());
-                } else if sel.starts_with("tc,") {
-                    let b: usize = sel.find("tc,").unwrap_or(0) + 3;
-                    let sr = sel[b..]
-                        .split(",")
-                        .map(|s| s.trim())
-                        .collect::<Vec<&str, 3>>();
-                    m[r][c] = TapCom::tcnew(sr[0].into(), (sr[1].into(), sr[2].into()));
-                } else if sel.starts_with("mc,") {
-                    let b: usize = sel.find("mc,").unwrap_or(0) + 3;
-                    let sr = sel[b..]
-                        .split(",")
-                        .map(|s| s.trim())
-                        .collect::<Vec<&str, 2>>();
-                    m[r][c] = ModCombo::mcnew(sr[0].into(), sr[1].into());
-                } else if sel.starts_with("rk,") {
-                    let b: usize = sel.find("rk,").unwrap_or(0) + 3;
-                    let sr = &sel[b..].split("_").map(|x| x.trim()).collect::<Vec<&str, 3>>();
-                    m[r][c] = RGBKey::rknew(sr[0].parse().unwrap(), sr[1].parse().unwrap(), sr[2].parse().unwrap());
-                } else {
-                    m[r][c] = Default::new("EEEEEEEE".into(), None);
-                }
+                m[r][c] = str_to_key(sel);
             }
             c += 1;
         });
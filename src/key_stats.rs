@@ -0,0 +1,125 @@
+use defmt::info;
+
+use crate::rgb::led_map::nearest_led;
+use crate::rgb::{self, NUM_LEDS};
+use crate::{storage, suspend, MATRIX_COLS, MATRIX_ROWS};
+
+/// Marks the settings sector as holding press counts, change it if the layout changes
+const MAGIC: u32 = 0x4b45_5931;
+/// Where the counts live in the settings sector, the magic followed by a u32 per key
+const STATS_OFFSET: usize = 0;
+const STATS_LEN: usize = 4 + MATRIX_ROWS * MATRIX_COLS * 4;
+/// Time(ms) without a press before changed counts are saved
+/// Erasing the settings sector stalls USB and the scan, so it waits for a break in typing
+const IDLE_SAVE_MS: u32 = 60 * 1000;
+
+/// Press count for every matrix position
+struct KeyStats {
+    counts: [[u32; MATRIX_COLS]; MATRIX_ROWS],
+    /// counts changed since the last save
+    dirty: bool,
+    /// time(ms) of the last press
+    pressed_at: u32,
+    /// save at the next poll without waiting for a break, set by clear
+    save_now: bool,
+}
+
+// Only touched from the main loop(matrix callback and HID-IO)
+static mut STATS: KeyStats = KeyStats {
+    counts: [[0; MATRIX_COLS]; MATRIX_ROWS],
+    dirty: false,
+    pressed_at: 0,
+    save_now: false,
+};
+
+/// read the counts saved in flash, starts from zero if there aren't any
+pub fn load() {
+    let mut buf = [0u8; STATS_LEN];
+    storage::read(STATS_OFFSET, &mut buf);
+    let stats = unsafe { &mut STATS };
+    if u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) == MAGIC {
+        for (count, bytes) in stats.counts.iter_mut().flatten().zip(buf[4..].chunks(4)) {
+            *count = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        info!("Loaded key press counts");
+    }
+    rgb::set_heat(heat());
+}
+
+/// count a press of the key at row, col(zero indexed) at time now(ms)
+pub fn key_pressed(row: usize, col: usize, now: u32) {
+    let stats = unsafe { &mut STATS };
+    if let Some(count) = stats.counts.get_mut(row).and_then(|r| r.get_mut(col)) {
+        *count = count.saturating_add(1);
+        stats.dirty = true;
+        stats.pressed_at = now;
+        rgb::set_heat(heat());
+    }
+}
+
+/// presses of the key at row, col(zero indexed)
+pub fn count(row: usize, col: usize) -> u32 {
+    let stats = unsafe { &STATS };
+    stats
+        .counts
+        .get(row)
+        .and_then(|r| r.get(col))
+        .copied()
+        .unwrap_or(0)
+}
+
+/// zero every count, saved by the next poll
+/// HID-IO commands run in a critical section, which the flash write mustn't
+pub fn clear() {
+    let stats = unsafe { &mut STATS };
+    stats.counts = [[0; MATRIX_COLS]; MATRIX_ROWS];
+    stats.dirty = true;
+    stats.save_now = true;
+    rgb::set_heat(heat());
+}
+
+/// save changed counts once nothing was pressed for IDLE_SAVE_MS, the host suspended or
+/// they were cleared, called from the main loop outside any critical section
+pub fn poll(now: u32) {
+    let stats = unsafe { &STATS };
+    if stats.dirty
+        && (stats.save_now
+            || suspend::is_suspended()
+            || now.wrapping_sub(stats.pressed_at) >= IDLE_SAVE_MS)
+    {
+        save();
+    }
+}
+
+/// save the counts now if they changed, e.g. before a reboot
+pub fn flush() {
+    if unsafe { STATS.dirty } {
+        save();
+    }
+}
+
+fn save() {
+    let stats = unsafe { &mut STATS };
+    let mut buf = [0u8; STATS_LEN];
+    buf[..4].copy_from_slice(&MAGIC.to_le_bytes());
+    for (bytes, count) in buf[4..].chunks_mut(4).zip(stats.counts.iter().flatten()) {
+        bytes.copy_from_slice(&count.to_le_bytes());
+    }
+    info!("Saving key press counts");
+    storage::write(&buf);
+    stats.dirty = false;
+    stats.save_now = false;
+}
+
+/// how hot each LED is(0-255), every key adds its count to the closest LED
+fn heat() -> [u8; NUM_LEDS] {
+    let stats = unsafe { &STATS };
+    let mut sums = [0u64; NUM_LEDS];
+    for (row, counts) in stats.counts.iter().enumerate() {
+        for (col, count) in counts.iter().enumerate() {
+            sums[nearest_led(row, col)] += *count as u64;
+        }
+    }
+    let max = sums.iter().copied().max().unwrap_or(0).max(1);
+    sums.map(|sum| (sum * 255 / max) as u8)
+}
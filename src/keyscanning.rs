@@ -7,11 +7,11 @@ use crate::mods::mod_tap::ModTap;
 use crate::mods::mod_tapcom::TapCom;
 use crate::mods::rgb_key::RGBKey;
 use defmt::{debug, error, info, println, warn, Format};
-use embedded_hal::digital::v2::{InputPin, OutputPin};
-use rp2040_hal::gpio::DynPin;
 use usbd_hid::descriptor::KeyboardReport;
 
 use crate::key::Default;
+use crate::matrix_test;
+use crate::scanner::{ScanBits, Scanner};
 use crate::{
     key::Key,
     key_codes::KeyCode,
@@ -33,47 +33,6 @@ pub enum StateType {
     Off = 3,
 }
 
-pub struct Col {
-    output: DynPin,
-}
-
-impl Col {
-    pub fn new(output: DynPin) -> Self {
-        let mut r: Col = Col { output };
-        r.output.into_push_pull_output();
-        r
-    }
-    pub fn set_high(&mut self) {
-        self.output.set_high().unwrap()
-    }
-    pub fn set_low(&mut self) {
-        self.output.set_low().unwrap()
-    }
-}
-
-pub struct Row {
-    input: DynPin,
-}
-
-impl Row {
-    pub fn new(input: DynPin) -> Self {
-        let mut r: Row = Row { input };
-        r.input.into_floating_input();
-        r
-    }
-    pub fn is_high(&mut self) -> bool {
-        self.input.is_high().unwrap()
-    }
-    pub fn is_low(&mut self) -> bool {
-        self.input.is_low().unwrap()
-    }
-    pub fn drain(&mut self) {
-        self.input.into_push_pull_output();
-        self.input.set_low().unwrap();
-        self.input.into_floating_input();
-    }
-}
-
 #[derive(Copy, Clone)]
 pub struct KeyMatrix<const RSIZE: usize, const CSIZE: usize> {
     matrix: [[Key; CSIZE]; RSIZE],
@@ -85,21 +44,18 @@ impl<const RSIZE: usize, const CSIZE: usize> KeyMatrix<RSIZE, CSIZE> {
     }
 }
 
-pub struct Matrix<const RSIZE: usize, const CSIZE: usize> {
-    rows: [Row; RSIZE],
-    cols: [Col; CSIZE],
+pub struct Matrix<const RSIZE: usize, const CSIZE: usize, S: Scanner<RSIZE>> {
+    scanner: S,
     state: KeyMatrix<RSIZE, CSIZE>,
     callback:
         fn(row: usize, col: usize, state: StateType, prevstate: StateType, keycodes: [KeyCode; 2]),
-    wait_cycles: u16,
-    cycles: u16,
-    cur_strobe: usize,
+    /// the switches closed in the last scan
+    bits: ScanBits<RSIZE>,
 }
 
-impl<const RSIZE: usize, const CSIZE: usize> Matrix<RSIZE, CSIZE> {
+impl<const RSIZE: usize, const CSIZE: usize, S: Scanner<RSIZE>> Matrix<RSIZE, CSIZE, S> {
     pub fn new(
-        rows: [Row; RSIZE],
-        cols: [Col; CSIZE],
+        scanner: S,
         callback: fn(
             row: usize,
             col: usize,
@@ -109,19 +65,14 @@ impl<const RSIZE: usize, const CSIZE: usize> Matrix<RSIZE, CSIZE> {
         ),
         keymap: KeyMatrix<RSIZE, CSIZE>,
     ) -> Self {
-        let mut new = Matrix {
-            rows,
-            cols,
+        assert!(CSIZE <= 32, "ScanBits holds a row in a u32");
+        Matrix {
+            scanner,
             // state: KeyMatrix::new([[Key::new(KeyCode::________, None); CSIZE]; RSIZE]),
             state: keymap,
             callback,
-            wait_cycles: 2,
-            cycles: 0,
-            cur_strobe: (CSIZE - 1),
-        };
-        new.cols[new.cur_strobe].set_high();
-        new.clear();
-        new
+            bits: ScanBits::new(),
+        }
     }
     fn execute_callback(
         &self,
@@ -133,81 +84,99 @@ impl<const RSIZE: usize, const CSIZE: usize> Matrix<RSIZE, CSIZE> {
     ) {
         (self.callback)(row, col, state, prevstate, keycodes);
     }
-    fn clear(&mut self) {
-        for r in self.cols.iter_mut() {
-            r.set_low();
-        }
+    /// replace the whole keymap
+    pub fn set_keymap(&mut self, keymap: KeyMatrix<RSIZE, CSIZE>) {
+        self.state = keymap;
     }
-    fn next_strobe(&mut self) {
-        // Unset current strobe
-        self.cols[self.cur_strobe].set_low();
-
-        // Drain stray potential from sense lines
-        for c in self.rows.iter_mut() {
-            c.drain();
+    /// read the raw state of every switch without running any key logic
+    /// used on boot before the keymap is doing anything
+    pub fn raw_scan(&mut self) -> [[bool; CSIZE]; RSIZE] {
+        let bits = self.scanner.scan();
+        let mut scan = [[false; CSIZE]; RSIZE];
+        for (r, row) in scan.iter_mut().enumerate() {
+            for (c, closed) in row.iter_mut().enumerate() {
+                *closed = bits.get(r, c);
+            }
         }
-
-        // Check overflow condition
-        if self.cur_strobe >= CSIZE - 1 {
-            self.cur_strobe = 0;
-        } else {
-            // Increment current strobe
-            self.cur_strobe += 1;
+        scan
+    }
+    /// the switches closed in the last poll
+    pub fn bits(&self) -> &ScanBits<RSIZE> {
+        &self.bits
+    }
+    /// get the key at a zero indexed matrix position
+    pub fn get_key(&self, row: usize, col: usize) -> Option<&Key> {
+        self.state.matrix.get(row).and_then(|r| r.get(col))
+    }
+    /// replace the key at a zero indexed matrix position
+    /// returns false if the position is outside of the matrix
+    pub fn set_key(&mut self, row: usize, col: usize, key: Key) -> bool {
+        match self.state.matrix.get_mut(row).and_then(|r| r.get_mut(col)) {
+            Some(k) => {
+                *k = key;
+                true
+            }
+            None => false,
         }
-
-        // Set new strobe as high
-        self.cols[self.cur_strobe].set_high();
-        // let mut str: String<10> = "strobing ".into();
-        // let strobe: String<10> = String::from(self.cur_strobe as u32);
-        // str.push_str(&strobe).unwrap();
-        // self.execute_info(&str)
     }
-    pub fn poll(&mut self, ctx: Context) -> bool {
-        self.next_strobe();
-        let c = self.cur_strobe;
-
+    /// scan the whole matrix and run the keys whose switch changed
+    /// keys that are still down or busy(e.g. a mod tap sending its tap) run every poll too,
+    /// their cycle counters are how they time holds
+    pub fn poll(&mut self, ctx: Context) {
+        let bits = self.scanner.scan();
+        // the test wants every switch as is, no keymap in the way
+        if matrix_test::is_active() {
+            for (r, c) in bits.changed(&self.bits) {
+                matrix_test::switch(r, c, bits.get(r, c));
+            }
+            self.bits = bits;
+            return;
+        }
+        let mut run = ScanBits::<RSIZE>::new();
+        for (r, c) in bits.changed(&self.bits) {
+            run.set(r, c, true);
+        }
+        self.bits = bits;
         for r in 0..RSIZE {
-            let codes: [Option<(KeyCode, Operation)>; 4];
-            let _typ: &str;
-            match self.state.matrix[r][c].typ {
-                "Default" => {
-                    codes = self.state.matrix[r][c].scan(self.rows[r].is_high(), ctx);
-                }
-                "ModTap" => {
-                    codes = self.state.matrix[r][c].mtscan(self.rows[r].is_high(), ctx);
-                }
-                "TapCom" => {
-                    codes = self.state.matrix[r][c].tcscan(self.rows[r].is_high(), ctx);
-                }
-                "ModCombo" => {
-                    codes = self.state.matrix[r][c].mcscan(self.rows[r].is_high(), ctx);
-                }
-                "RGBKey" => {
-                    codes = self.state.matrix[r][c].rkscan(self.rows[r].is_high(), ctx);
-                }
-                _ => {
-                    codes = [None; 4];
-                    error!("Unknown key type {}", self.state.matrix[r][c].typ);
+            for c in 0..CSIZE {
+                if run.get(r, c) || !self.state.matrix[r][c].is_settled() {
+                    self.scan_key(r, c, bits.get(r, c), ctx);
                 }
             }
-            if self.state.matrix[r][c].state != self.state.matrix[r][c].prevstate {
-                self.execute_callback(
-                    r + 1,
-                    c + 1,
-                    self.state.matrix[r][c].state,
-                    self.state.matrix[r][c].prevstate,
-                    // [KeyCode::________, KeyCode::________],
-                    [
-                        codes[0].unwrap_or((KeyCode::________, Operation::SendOn)).0,
-                        codes[1].unwrap_or((KeyCode::________, Operation::SendOn)).0,
-                    ],
-                );
-            }
         }
-        if self.state.matrix[0][0].raw_state {
-            return true;
+    }
+    /// run the key at row, col(zero indexed) with the state of its switch
+    fn scan_key(&mut self, r: usize, c: usize, is_high: bool, ctx: Context) {
+        let codes = run_key(&mut self.state.matrix[r][c], is_high, ctx);
+        if self.state.matrix[r][c].state != self.state.matrix[r][c].prevstate {
+            self.execute_callback(
+                r + 1,
+                c + 1,
+                self.state.matrix[r][c].state,
+                self.state.matrix[r][c].prevstate,
+                // [KeyCode::________, KeyCode::________],
+                [
+                    codes[0].unwrap_or((KeyCode::________, Operation::SendOn)).0,
+                    codes[1].unwrap_or((KeyCode::________, Operation::SendOn)).0,
+                ],
+            );
+        }
+    }
+}
+
+/// run a key of any type with the state of its switch
+/// anything that acts like a switch(e.g. an encoder step) goes through here like the matrix
+pub fn run_key(key: &mut Key, is_high: bool, ctx: Context) -> [Option<(KeyCode, Operation)>; 4] {
+    match key.typ {
+        "Default" => key.scan(is_high, ctx),
+        "ModTap" => key.mtscan(is_high, ctx),
+        "TapCom" => key.tcscan(is_high, ctx),
+        "ModCombo" => key.mcscan(is_high, ctx),
+        "RGBKey" => key.rkscan(is_high, ctx),
+        _ => {
+            error!("Unknown key type {}", key.typ);
+            [None; 4]
         }
-        false
     }
 }
 
@@ -303,3 +272,52 @@ impl<const QSIZE: usize> KeyQueue<QSIZE> {
         keys
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host_leds::HostLeds;
+    use crate::key::{DEBOUNCE_CYCLES, HOLD_CYCLES};
+    use crate::scanner::FakeScanner;
+    use std::sync::Mutex;
+    use std::vec::Vec;
+
+    type Event = (usize, usize, StateType, StateType);
+    static EVENTS: Mutex<Vec<Event>> = Mutex::new(Vec::new());
+
+    fn callback(row: usize, col: usize, state: StateType, prev: StateType, _: [KeyCode; 2]) {
+        EVENTS.lock().unwrap().push((row, col, state, prev));
+    }
+
+    fn events() -> Vec<Event> {
+        core::mem::take(&mut *EVENTS.lock().unwrap())
+    }
+
+    #[test]
+    fn poll_runs_the_keys_of_changed_switches() {
+        let ctx = Context {
+            key_queue: [None; 10],
+            host_leds: HostLeds::default(),
+            layer: 0,
+        };
+        let keymap = KeyMatrix::new([[Key::new(KeyCode::________, None); 2]; 2]);
+        let mut matrix = Matrix::new(FakeScanner::<2>::new(), callback, keymap);
+        matrix.scanner.press(1, 0);
+        for _ in 1..DEBOUNCE_CYCLES {
+            matrix.poll(ctx);
+        }
+        assert_eq!(events(), []);
+        matrix.poll(ctx);
+        // the callback gets one indexed positions
+        assert_eq!(events(), [(2, 1, StateType::Tap, StateType::Off)]);
+        for _ in DEBOUNCE_CYCLES..HOLD_CYCLES {
+            matrix.poll(ctx);
+        }
+        assert_eq!(events(), [(2, 1, StateType::Hold, StateType::Tap)]);
+        matrix.scanner.release(1, 0);
+        matrix.poll(ctx);
+        matrix.poll(ctx);
+        assert_eq!(events(), [(2, 1, StateType::Off, StateType::Hold)]);
+        assert!(matrix.get_key(1, 0).unwrap().is_settled());
+    }
+}
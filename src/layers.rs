@@ -0,0 +1,76 @@
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use defmt::info;
+
+use crate::key_codes::KeyCode;
+
+/// Layers of the keymap, 0 is the base layer
+pub const LAYERS: usize = 3;
+
+/// Whether Lyr_Mo1z is down
+static HELD: AtomicBool = AtomicBool::new(false);
+/// Whether Lyr_Next is down, a held key presses again every poll
+static NEXT_DOWN: AtomicBool = AtomicBool::new(false);
+/// The layer Lyr_Next switched to
+static SWITCHED: AtomicU8 = AtomicU8::new(0);
+
+/// press or release a layer key, returns false for any other key
+/// only called from the main loop through action()
+pub fn handle_key(code: KeyCode, pressed: bool) -> bool {
+    match code {
+        KeyCode::Lyr_Mo1z => HELD.store(pressed, Ordering::Relaxed),
+        KeyCode::Lyr_Next => {
+            // only the first press of a hold switches
+            if pressed && !NEXT_DOWN.load(Ordering::Relaxed) {
+                let next = (SWITCHED.load(Ordering::Relaxed) + 1) % LAYERS as u8;
+                SWITCHED.store(next, Ordering::Relaxed);
+                info!("Switched to layer {}", next);
+            }
+            NEXT_DOWN.store(pressed, Ordering::Relaxed);
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// the layer the keys are on, layer 1 while Lyr_Mo1z is held and else the one switched to
+pub fn active() -> u8 {
+    if HELD.load(Ordering::Relaxed) {
+        1
+    } else {
+        SWITCHED.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{active, handle_key};
+    use crate::key_codes::KeyCode;
+
+    #[test]
+    fn held_and_switched_layers() {
+        assert!(!handle_key(KeyCode::Ltr_Azzz, true));
+        assert_eq!(active(), 0);
+        handle_key(KeyCode::Lyr_Mo1z, true);
+        assert_eq!(active(), 1);
+        handle_key(KeyCode::Lyr_Mo1z, false);
+        assert_eq!(active(), 0);
+        // the repeats of a held key don't switch again
+        for _ in 0..3 {
+            handle_key(KeyCode::Lyr_Next, true);
+        }
+        handle_key(KeyCode::Lyr_Next, false);
+        assert_eq!(active(), 1);
+        handle_key(KeyCode::Lyr_Next, true);
+        handle_key(KeyCode::Lyr_Next, false);
+        assert_eq!(active(), 2);
+        // a held layer wins over the switched one until it is let go
+        handle_key(KeyCode::Lyr_Mo1z, true);
+        assert_eq!(active(), 1);
+        handle_key(KeyCode::Lyr_Mo1z, false);
+        assert_eq!(active(), 2);
+        handle_key(KeyCode::Lyr_Next, true);
+        handle_key(KeyCode::Lyr_Next, false);
+        assert_eq!(active(), 0);
+    }
+}
@@ -1,42 +1,93 @@
-#![no_std]
-#![no_main]
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(test), no_main)]
 #![allow(non_snake_case)]
 #![feature(generic_const_exprs)]
 
 mod actions;
+mod board;
+mod boot;
+mod bootloader;
+#[cfg(feature = "encoders")]
+mod encoder;
+mod hidio;
+mod host_leds;
 mod key;
 mod key_codes;
 mod key_mapping;
+mod key_stats;
 mod keyscanning;
+mod layers;
 mod macros;
+mod matrix_test;
 mod mods;
+mod rgb;
+mod scanner;
+#[cfg(feature = "split")]
+mod split;
+mod storage;
+mod suspend;
 mod util;
+mod wpm;
 
 use core::sync::atomic::AtomicBool;
 use core::{
     cell::RefCell,
-    sync::atomic::{AtomicU8, Ordering},
+    sync::atomic::{AtomicU32, Ordering},
 };
 
 use crate::{key_codes::KeyCode, pac::interrupt};
+use core::fmt::Write;
+#[cfg(not(test))]
 use cortex_m_rt::entry;
 use defmt::*;
 use defmt_rtt as _;
 use heapless::String;
-use keyscanning::{Col, Row};
-use kiibohd_hid_io::{CommandInterface, HidIoCommandId, KiibohdCommandInterface};
+use hidio::{Dump, HidioCmd, HidioInterface};
+use host_leds::HostLeds;
+use kiibohd_hid_io::{h0034, CommandInterface, Commands, HidIoCommandId};
 use kiibohd_usb::KeyState;
+#[cfg(not(test))]
 use panic_probe as _;
+// the firmware gets this from defmt.x, host tests have to define it
+#[cfg(test)]
+defmt::timestamp!("");
+#[cfg(all(feature = "pio-scan", feature = "split"))]
+compile_error!("the split halves scan from core 0, pio-scan doesn't support them yet");
+#[cfg(all(feature = "direct-pins", any(feature = "pio-scan", feature = "split")))]
+compile_error!("direct-pins boards have no matrix to strobe or split");
+#[cfg(all(feature = "hand-strap", feature = "hand-flash"))]
+compile_error!("pick one of hand-strap and hand-flash");
+#[cfg(all(feature = "sense-pull-up", feature = "sense-pull-down"))]
+compile_error!("pick one of sense-pull-up and sense-pull-down");
+#[cfg(all(
+    feature = "pio-scan",
+    any(
+        feature = "row2col",
+        feature = "sense-pull-up",
+        feature = "sense-pull-down"
+    )
+))]
+compile_error!("the PIO program strobes the columns high and drains the floating rows");
+
+#[cfg(feature = "direct-pins")]
+use scanner::DirectScanner;
+#[cfg(not(any(feature = "pio-scan", feature = "direct-pins")))]
+use scanner::GpioScanner;
+#[cfg(feature = "pio-scan")]
+use scanner::PioScanner;
+use scanner::{ChatterFilter, GhostFilter};
 use usb_device::prelude::{UsbDeviceBuilder, UsbVidPid};
 use util::hid_descriptor::KeyboardNkroReport;
+use wpm::Wpm;
 
 use critical_section::Mutex;
-use heapless::spsc::{Producer, Queue};
+use heapless::spsc::{Consumer, Producer, Queue};
 use usb_device::{class_prelude::UsbBusAllocator, prelude::UsbDevice, UsbError};
-use usbd_hid::hid_class::HidCountryCode;
+use usbd_hid::hid_class::{HidCountryCode, HidProtocolMode, ProtocolModeConfig};
 
 use rp2040_hal::{
     clocks::{init_clocks_and_plls, Clock},
+    gpio::DynPin,
     multicore::{Multicore, Stack},
     pac,
     prelude::_rphal_pio_PIOExt,
@@ -51,14 +102,17 @@ use crate::keyscanning::StateType;
 use crate::keyscanning::{Matrix, Operation};
 
 use self::actions::CallbackActions;
+use self::boot::BootAction;
 use self::keyscanning::KeyQueue;
 
 // These define the maximum pending items in each queue
 const KBD_QUEUE_SIZE: usize = 10; // This would limit NKRO mode to 10KRO
-const KBD_LED_QUEUE_SIZE: usize = 3;
+const KBD_LED_QUEUE_SIZE: usize = 6; // One per host LED(5) change in a single report, drained after every pull
 const MOUSE_QUEUE_SIZE: usize = 5;
 const CTRL_QUEUE_SIZE: usize = 2;
 
+use board::{MATRIX_COLS, MATRIX_ROWS};
+
 type HidInterface = kiibohd_usb::HidInterface<
     'static,
     UsbBus,
@@ -68,29 +122,13 @@ type HidInterface = kiibohd_usb::HidInterface<
     CTRL_QUEUE_SIZE,
 >;
 
-pub struct HidioInterface<const H: usize> {}
-
-#[allow(dead_code)]
-impl<const H: usize> HidioInterface<H> {
-    fn new() -> Self {
-        Self {}
-    }
-}
-
-impl<const H: usize> KiibohdCommandInterface<H> for HidioInterface<H> {
-    fn h0001_device_name(&self) -> Option<&str> {
-        Some("ErgoOne")
-    }
-
-    fn h0001_firmware_name(&self) -> Option<&str> {
-        Some("ErgoOne")
-    }
-}
+type HidioCommandInterface = CommandInterface<HidioInterface<256>, 8, 8, 64, 256, 277, 10>;
 
 #[derive(Clone, PartialEq, PartialOrd)]
 pub enum ARGS {
     KS { code: KeyCode, op: Operation },
     RGB { r: u8, g: u8, b: u8 },
+    KeyRGB { key: (u8, u8), r: u8, g: u8, b: u8 },
     STR { s: String<30> },
 }
 
@@ -98,11 +136,20 @@ pub enum ARGS {
 pub fn action(action: CallbackActions, ops: ARGS) {
     match action {
         CallbackActions::Press => match ops {
+            ARGS::KS {
+                code: KeyCode::Fun_Boot,
+                ..
+            } => bootloader::request(),
+            // the host is asleep, the press only wakes it(see suspend::key_pressed)
+            ARGS::KS { .. } if suspend::is_suspended() => {}
+            ARGS::KS { code, .. } if rgb::handle_key(code) => {}
+            ARGS::KS { code, .. } if layers::handle_key(code, true) => {}
             ARGS::KS { code, op } => {
                 critical_section::with(|_| {
                     let kbd = unsafe { KBD_PRODUCER.get_mut() };
                     if code != KeyCode::________ {
                         if kbd.is_some() {
+                            shift_update(kbd.as_mut().unwrap(), code, true);
                             match kbd
                                 .as_mut()
                                 .unwrap()
@@ -129,6 +176,8 @@ pub fn action(action: CallbackActions, ops: ARGS) {
             }
         },
         CallbackActions::Release => match ops {
+            ARGS::KS { code, .. } if layers::handle_key(code, false) => {}
+            ARGS::KS { code, .. } if code.is_firmware() => {}
             ARGS::KS { code, op } => {
                 critical_section::with(|_| {
                     let kbd = unsafe { KBD_PRODUCER.get_mut() };
@@ -145,6 +194,7 @@ pub fn action(action: CallbackActions, ops: ARGS) {
                                 }
                                 Err(err) => error!("{}", err),
                             }
+                            shift_update(kbd.as_mut().unwrap(), code, false);
                         } else {
                             error!("KBD_PRODUCER is None");
                         }
@@ -157,15 +207,29 @@ pub fn action(action: CallbackActions, ops: ARGS) {
         },
         CallbackActions::RGBSet => match ops {
             ARGS::RGB { r, g, b } => {
-                println!("RGB: {} {} {}", r, g, b);
-                RCOL.store(r, Ordering::Relaxed);
-                GCOL.store(g, Ordering::Relaxed);
-                BCOL.store(b, Ordering::Relaxed);
+                defmt::println!("RGB: {} {} {}", r, g, b);
+                rgb::set_color(r, g, b);
             }
             _ => {
                 error!("Expected ARGS::RGB but got something else");
             }
         },
+        CallbackActions::RGBKeySet => match ops {
+            ARGS::KeyRGB {
+                key: (row, col),
+                r,
+                g,
+                b,
+            } => {
+                let color = smart_leds::RGB8::new(r, g, b);
+                if !rgb::toggle_key_color(row as usize, col as usize, color) {
+                    warn!("No key at {} {}", row, col);
+                }
+            }
+            _ => {
+                error!("Expected ARGS::KeyRGB but got something else");
+            }
+        },
         CallbackActions::SendString => match ops {
             ARGS::STR { s: _ } => {}
             _ => {
@@ -187,12 +251,12 @@ pub static BOOT_LOADER: [u8; 256] = rp2040_boot2::BOOT_LOADER_W25Q080;
 static mut CTRL_QUEUE: Queue<kiibohd_usb::CtrlState, CTRL_QUEUE_SIZE> = Queue::new();
 static mut KBD_QUEUE: Queue<kiibohd_usb::KeyState, KBD_QUEUE_SIZE> = Queue::new();
 static mut KBD_LED_QUEUE: Queue<kiibohd_usb::LedState, KBD_LED_QUEUE_SIZE> = Queue::new();
+static mut KBD_LED_CONSUMER: Option<Consumer<'static, kiibohd_usb::LedState, KBD_LED_QUEUE_SIZE>> =
+    None;
 static mut MOUSE_QUEUE: Queue<kiibohd_usb::MouseState, MOUSE_QUEUE_SIZE> = Queue::new();
-static mut HIDIO_INTF: Mutex<
-    Option<CommandInterface<HidioInterface<256>, 8, 8, 64, 256, 277, 10>>,
-> = Mutex::new(None);
+static mut HIDIO_INTF: Mutex<Option<HidioCommandInterface>> = Mutex::new(None);
 
-#[entry]
+#[cfg_attr(not(test), entry)]
 fn main() -> ! {
     info!("Program start");
     // Initialize everything
@@ -213,7 +277,11 @@ fn main() -> ! {
     .ok()
     .unwrap();
     let mut delay = cortex_m::delay::Delay::new(core.SYST, clocks.system_clock.freq().to_Hz());
-    let timer = Timer::new(pac.TIMER, &mut pac.RESETS);
+    unsafe {
+        TIMER = Some(Timer::new(pac.TIMER, &mut pac.RESETS));
+    }
+    #[cfg(feature = "double-tap-reset")]
+    bootloader::check_double_tap(&mut delay);
     let pins = rp2040_hal::gpio::Pins::new(
         pac.IO_BANK0,
         pac.PADS_BANK0,
@@ -239,6 +307,7 @@ fn main() -> ! {
         let (mouse_producer, mouse_consumer) = MOUSE_QUEUE.split();
         let (ctrl_producer, ctrl_consumer) = CTRL_QUEUE.split();
         KBD_PRODUCER = Mutex::new(Some(kbd_producer));
+        KBD_LED_CONSUMER = Some(kbd_led_consumer);
         USB_HID = Some(HidInterface::new(
             USB_ALLOCATOR.as_ref().unwrap(),
             HidCountryCode::US,
@@ -251,11 +320,14 @@ fn main() -> ! {
 
         // Basic CommandInterface
         HIDIO_INTF = Mutex::new(Some(
-            CommandInterface::<HidioInterface<256>, 8, 8, 64, 256, 277, 10>::new(
+            HidioCommandInterface::new(
                 &[
                     HidIoCommandId::SupportedIds,
                     HidIoCommandId::GetInfo,
                     HidIoCommandId::TestPacket,
+                    HidIoCommandId::FlashMode,
+                    HidIoCommandId::TerminalCmd,
+                    HidIoCommandId::TerminalOut,
                 ],
                 HidioInterface::<256>::new(),
             )
@@ -268,11 +340,12 @@ fn main() -> ! {
                 .manufacturer("HisZd")
                 .product("ErgoOne")
                 .serial_number("000001")
-                .supports_remote_wakeup(false)
+                .supports_remote_wakeup(true)
+                .max_power(rgb::power::USB_MAX_POWER_MA)
                 .build(),
         );
         warn!("HID_BUS is setup");
-    }
+    };
 
     // Enable the USB interrupt
     unsafe {
@@ -280,31 +353,73 @@ fn main() -> ! {
     };
 
     // Initialize Keyscanning
-    let rows: [Row; 5] = [
-        Row::new(pins.gpio15.into()),
-        Row::new(pins.gpio14.into()),
-        Row::new(pins.gpio13.into()),
-        Row::new(pins.gpio12.into()),
-        Row::new(pins.gpio11.into()),
-    ];
-    let cols: [Col; 16] = [
-        Col::new(pins.gpio29.into()),
-        Col::new(pins.gpio28.into()),
-        Col::new(pins.gpio27.into()),
-        Col::new(pins.gpio26.into()),
-        Col::new(pins.gpio25.into()),
-        Col::new(pins.gpio24.into()),
-        Col::new(pins.gpio23.into()),
-        Col::new(pins.gpio17.into()),
-        Col::new(pins.gpio16.into()),
-        Col::new(pins.gpio6.into()),
-        Col::new(pins.gpio5.into()),
-        Col::new(pins.gpio4.into()),
-        Col::new(pins.gpio3.into()),
-        Col::new(pins.gpio2.into()),
-        Col::new(pins.gpio1.into()),
-        Col::new(pins.gpio0.into()),
-    ];
+    #[cfg(not(any(feature = "split", feature = "direct-pins")))]
+    let (rows, cols): ([DynPin; MATRIX_ROWS], [DynPin; MATRIX_COLS]) = board::matrix_pins!(pins);
+    // The matrix is strobed from core 0 unless PIO1 is doing it in hardware
+    #[cfg(not(any(feature = "pio-scan", feature = "split", feature = "direct-pins")))]
+    let scanner = GpioScanner::new(
+        rows,
+        cols,
+        board::DIODES,
+        board::SENSE,
+        clocks.system_clock.freq().to_Hz(),
+    );
+    #[cfg(feature = "pio-scan")]
+    let scanner = {
+        use rp2040_hal::dma::DMAExt;
+        let (pio1, sm0, _, _, _) = pac.PIO1.split(&mut pac.RESETS);
+        let dma = pac.DMA.split(&mut pac.RESETS);
+        PioScanner::new(
+            pio1,
+            sm0,
+            dma.ch0,
+            dma.ch1,
+            rows,
+            cols,
+            clocks.system_clock.freq().to_Hz(),
+        )
+    };
+    #[cfg(feature = "direct-pins")]
+    let scanner = DirectScanner::new(board::direct_pins!(pins), board::SENSE);
+    // Each half scans its own columns and the link joins them into one matrix
+    #[cfg(feature = "split")]
+    let scanner = {
+        use fugit::RateExtU32;
+        use rp2040_hal::uart::{DataBits, StopBits, UartConfig, UartPeripheral};
+        use split::hand::HandSource;
+        let (rows, cols): ([DynPin; MATRIX_ROWS], [DynPin; board::HALF_COLS]) =
+            board::half_matrix_pins!(pins);
+        let uart = UartPeripheral::new(
+            pac.UART1,
+            (pins.gpio8.into_mode(), pins.gpio9.into_mode()),
+            &mut pac.RESETS,
+        )
+        .enable(
+            UartConfig::new(460_800.Hz(), DataBits::Eight, None, StopBits::One),
+            clocks.peripheral_clock.freq(),
+        )
+        .unwrap();
+        let hand = match board::HAND_FROM {
+            #[cfg(feature = "hand-strap")]
+            HandSource::Strap => Some(split::hand::from_strap(board::hand_strap!(pins))),
+            #[cfg(feature = "hand-flash")]
+            HandSource::Flash => split::hand::load(),
+            #[cfg(not(any(feature = "hand-strap", feature = "hand-flash")))]
+            HandSource::Usb => None,
+        };
+        split::SplitScanner::new(
+            GpioScanner::new(
+                rows,
+                cols,
+                board::DIODES,
+                board::SENSE,
+                clocks.system_clock.freq().to_Hz(),
+            ),
+            split::SplitLink::new(split::link::UartLink::new(uart)),
+            hand,
+            millis,
+        )
+    };
     /// callback to print a report of what happened during the scan
     fn callback(
         row: usize,
@@ -334,22 +449,61 @@ fn main() -> ! {
         })
         .unwrap();
         info!("{}, c1: {}, c2: {}", str, keycodes[0], keycodes[1]);
+        if state != StateType::Idle {
+            rgb::key_activity(millis());
+        }
+        if state == StateType::Tap {
+            suspend::key_pressed(row - 1, col - 1);
+            rgb::key_pressed(millis(), row - 1, col - 1);
+            key_stats::key_pressed(row - 1, col - 1, millis());
+            // only keys the host sees count, modifiers don't type anything on their own
+            if u8::from(keycodes[0]) != 0 && !keycodes[0].is_modifier() {
+                unsafe { WPM.press(millis()) };
+            }
+        }
     }
 
-    let mut matrix: Matrix<5, 16> =
-        Matrix::new(rows, cols, callback, key_mapping::ERGOONE_RSTLNE.into());
-    let poll1 = matrix.poll(Context {
-        key_queue: unsafe { ACTIVE_QUEUE.get_keys() },
-    });
+    let mut matrix: Matrix<MATRIX_ROWS, MATRIX_COLS, _> = Matrix::new(
+        GhostFilter::new(
+            ChatterFilter::new(scanner, millis, board::CHATTER_DEBOUNCE),
+            board::GHOST_CHECK,
+        ),
+        callback,
+        key_mapping::ERGOONE_RSTLNE.into(),
+    );
+
+    #[cfg(feature = "encoders")]
+    let mut encoders = encoder::Encoders::new(
+        board::encoder_pins!(pins),
+        board::ENCODER_RESOLUTION,
+        key_mapping::encoder_keys(key_mapping::ENCODER_MAP),
+    );
 
-    if poll1 {
-        // let gpio_activity_pin_mask = 0;
-        // let disable_interface_mask = 0;
-        info!("Escape key detected on boot, going into bootloader mode.");
-        // rp2040_hal::rom_data::reset_to_usb_boot(gpio_activity_pin_mask, disable_interface_mask);
-        rp2040_hal::rom_data::reset_to_usb_boot(0, 0);
+    let scan = matrix.raw_scan();
+    for boot_action in boot::held_actions(&scan) {
+        info!("Key held on boot: {}", boot_action);
+        match boot_action {
+            BootAction::Bootloader => {
+                info!("Escape key detected on boot, going into bootloader mode.");
+                bootloader::enter();
+            }
+            BootAction::ClearSettings => storage::erase(),
+            BootAction::LedsOff => LEDS_ENABLED.store(false, Ordering::Relaxed),
+            BootAction::BootProtocol => unsafe {
+                if let Some(usb_hid) = USB_HID.as_mut() {
+                    usb_hid.set_kbd_protocol_mode(
+                        HidProtocolMode::Boot,
+                        ProtocolModeConfig::ForceBoot,
+                    );
+                }
+            },
+            BootAction::Qwerty => matrix.set_keymap(key_mapping::ERGOONE_QWERTY.into()),
+            BootAction::MatrixTest => matrix_test::start(),
+        }
     }
 
+    key_stats::load();
+
     let mut mc = Multicore::new(&mut pac.PSM, &mut pac.PPB, &mut sio.fifo);
 
     let cores = mc.cores();
@@ -358,43 +512,54 @@ fn main() -> ! {
     let _ledcore = core1.spawn(unsafe { &mut CORE1_STACK.mem }, move || {
         use smart_leds::{SmartLedsWrite, RGB8};
         let (mut pio, sm0, _, _, _) = pac.PIO0.split(&mut pac.RESETS);
-        let empty: [RGB8; 8] = [RGB8::default(); 8];
+        let empty: [RGB8; rgb::NUM_LEDS] = [RGB8::default(); rgb::NUM_LEDS];
         let mut ws = Ws2812::new(
             pins.gpio7.into_mode(),
             &mut pio,
             sm0,
             clocks.peripheral_clock.freq(),
-            timer.count_down(),
+            unsafe { TIMER.as_ref().unwrap() }.count_down(),
         );
-        let mut R = RCOL.load(Ordering::Relaxed);
-        let mut G = GCOL.load(Ordering::Relaxed);
-        let mut B = BCOL.load(Ordering::Relaxed);
+        let mut frame: [RGB8; rgb::NUM_LEDS] = [RGB8::default(); rgb::NUM_LEDS];
+        let mut refresh = rgb::Refresh::new();
         ws.write(empty.iter().copied()).unwrap();
+        storage::core1_ready();
         loop {
-            let color = [RGB8::new(R, G, B); 8];
-            R = RCOL.load(Ordering::Relaxed);
-            G = GCOL.load(Ordering::Relaxed);
-            B = BCOL.load(Ordering::Relaxed);
-            let curcol = [RGB8::new(R, G, B); 8];
-            if (curcol[0].r != color[0].r)
-                || (curcol[0].g != color[0].g)
-                || (curcol[0].b != color[0].b)
-            {
-                println!(
-                    "{},{},{}  {},{},{}",
-                    curcol[0].r, curcol[0].g, curcol[0].b, color[0].r, color[0].g, color[0].b
-                );
-                // ws.write(empty.iter().copied()).unwrap();
-                // color = curcol;
+            storage::core1_poll();
+            // only talk to the PIO when core 0 sent something new or an animation ticks
+            let now = millis();
+            let enabled = LEDS_ENABLED.load(Ordering::Relaxed);
+            if !refresh.due(now, enabled) {
+                continue;
             }
-            ws.write(color.iter().copied()).unwrap();
+            if !enabled {
+                ws.write(empty.iter().copied()).unwrap();
+                continue;
+            }
+            refresh.draw(now, &mut frame);
+            ws.write(frame.iter().copied()).unwrap();
         }
     });
 
+    // what is being dumped over HID-IO and the position of the next key to send
+    let mut dump: Option<(Dump, usize)> = None;
+
     info!("Loop starting!");
-    println!("thg = {}", 0 * 1);
+    defmt::println!("thg = {}", 0 * 1);
     loop {
-        delay.delay_us(1000u32);
+        // scan slower while the host is asleep, a key press only has to wake it
+        if suspend::is_suspended() {
+            delay.delay_us(suspend::SUSPENDED_POLL_US);
+            if suspend::take_wakeup(millis()) {
+                critical_section::with(|_| unsafe {
+                    if let Some(usb_dev) = HID_BUS.as_ref() {
+                        usb_dev.bus().remote_wakeup();
+                    }
+                });
+            }
+        } else {
+            delay.delay_us(1000u32);
+        }
         unsafe {
             if !RM_QUEUE.is_empty() {
                 let kbd = unsafe { KBD_PRODUCER.get_mut() };
@@ -408,6 +573,7 @@ fn main() -> ! {
                             Ok(_) => {
                                 warn!("Key REMOVED {:?}", k.unwrap());
                                 RM_QUEUE.dequeue(k.unwrap());
+                                shift_update(kbd.as_mut().unwrap(), k.unwrap().0, false);
                             }
                             Err(err) => error!("{}", err),
                         }
@@ -437,22 +603,334 @@ fn main() -> ! {
                 }
             }
         }
-        matrix.poll(Context {
-            key_queue: unsafe { ACTIVE_QUEUE.get_keys() },
+        critical_section::with(|_| {
+            let Some(hidio) = (unsafe { HIDIO_INTF.get_mut() }).as_mut() else {
+                return;
+            };
+            if let Some((kind, pos)) = dump {
+                // only send as much as the tx buffer can hold, the rest goes next loop
+                if pos >= MATRIX_ROWS * MATRIX_COLS {
+                    hidio_out(hidio, "end");
+                    dump = None;
+                } else if !hidio.tx_bytebuf.is_full() {
+                    let (row, col) = (pos / MATRIX_COLS, pos % MATRIX_COLS);
+                    let mut line: String<64> = String::new();
+                    match kind {
+                        Dump::Keymap => {
+                            if let Some(key) = matrix.get_key(row, col) {
+                                let entry = key_mapping::key_to_str(key);
+                                core::write!(line, "{} {} {}", row, col, entry).ok();
+                            }
+                        }
+                        Dump::Stats => {
+                            let count = key_stats::count(row, col);
+                            core::write!(line, "{} {} {}", row, col, count).ok();
+                        }
+                        Dump::Chatter => {
+                            let count = scanner::chatter::count(row, col);
+                            if count > 0 {
+                                core::write!(line, "{} {} {}", row, col, count).ok();
+                            }
+                            if scanner::chatter::is_flagged(row, col) {
+                                line.push_str(" worn").ok();
+                            }
+                        }
+                    }
+                    if !line.is_empty() {
+                        hidio_out(hidio, &line);
+                    }
+                    dump = Some((kind, pos + 1));
+                }
+            } else if let Some(cmd) = hidio.mut_interface().cmds.pop_front() {
+                info!("HID-IO command {}", cmd);
+                match cmd {
+                    HidioCmd::Keymap => dump = Some((Dump::Keymap, 0)),
+                    HidioCmd::Stats => dump = Some((Dump::Stats, 0)),
+                    HidioCmd::Chatter => dump = Some((Dump::Chatter, 0)),
+                    HidioCmd::ChatterClear => {
+                        scanner::chatter::clear();
+                        hidio_out(hidio, "ok");
+                    }
+                    HidioCmd::Wpm => {
+                        let mut line: String<8> = String::new();
+                        core::write!(line, "{}", unsafe { WPM.wpm(millis()) }).ok();
+                        hidio_out(hidio, &line);
+                    }
+                    HidioCmd::StatsClear => {
+                        key_stats::clear();
+                        hidio_out(hidio, "ok");
+                    }
+                    HidioCmd::SetKey { row, col, entry } => {
+                        if matrix.set_key(row, col, key_mapping::str_to_key(&entry)) {
+                            hidio_out(hidio, "ok");
+                        } else {
+                            hidio_out(hidio, "err position out of range");
+                        }
+                    }
+                    HidioCmd::Rgb { r, g, b } => {
+                        action(CallbackActions::RGBSet, ARGS::RGB { r, g, b });
+                        hidio_out(hidio, "ok");
+                    }
+                    HidioCmd::Hsv(hsv) => {
+                        rgb::set_hsv(hsv);
+                        hidio_out(hidio, "ok");
+                    }
+                    HidioCmd::Effect(effect) => {
+                        rgb::set_effect(effect);
+                        hidio_out(hidio, "ok");
+                    }
+                    HidioCmd::Speed(speed) => {
+                        rgb::set_speed(speed);
+                        hidio_out(hidio, "ok");
+                    }
+                    HidioCmd::SpeedWpm => {
+                        rgb::set_speed_wpm();
+                        hidio_out(hidio, "ok");
+                    }
+                    HidioCmd::Brightness(brightness) => {
+                        rgb::set_brightness(brightness);
+                        hidio_out(hidio, "ok");
+                    }
+                    HidioCmd::IdleTimeout(seconds) => {
+                        rgb::set_idle_timeout(seconds);
+                        hidio_out(hidio, "ok");
+                    }
+                    HidioCmd::Wakeup { row, col, enabled } => {
+                        if suspend::set_wakeup(row, col, enabled) {
+                            hidio_out(hidio, "ok");
+                        } else {
+                            hidio_out(hidio, "err position out of range");
+                        }
+                    }
+                    HidioCmd::Frame(colors) => {
+                        rgb::set_frame(colors.map(|(r, g, b)| smart_leds::RGB8::new(r, g, b)));
+                        hidio_out(hidio, "ok");
+                    }
+                    HidioCmd::MatrixTest(true) => {
+                        // whatever is held now would stay down on the host during the test
+                        release_all_keys();
+                        matrix_test::start();
+                        hidio_out(hidio, "ok");
+                    }
+                    HidioCmd::MatrixTest(false) => {
+                        matrix_test::stop();
+                        hidio_out(hidio, "ok");
+                    }
+                    #[cfg(feature = "split")]
+                    HidioCmd::Hand => {
+                        let mut line: String<16> = String::new();
+                        let hand = match split::hand::current() {
+                            Some(split::hand::Hand::Left) => "left",
+                            Some(split::hand::Hand::Right) => "right",
+                            None => "unknown",
+                        };
+                        let from = match board::HAND_FROM {
+                            #[cfg(feature = "hand-strap")]
+                            split::hand::HandSource::Strap => "strap",
+                            #[cfg(feature = "hand-flash")]
+                            split::hand::HandSource::Flash => "flash",
+                            #[cfg(not(any(feature = "hand-strap", feature = "hand-flash")))]
+                            split::hand::HandSource::Usb => "usb",
+                        };
+                        core::write!(line, "{} {}", hand, from).ok();
+                        hidio_out(hidio, &line);
+                    }
+                    #[cfg(feature = "split")]
+                    HidioCmd::SetHand(hand) => {
+                        split::hand::save(hand);
+                        hidio_out(hidio, "ok");
+                    }
+                    HidioCmd::BootloaderArm => {
+                        bootloader::arm(millis());
+                        hidio_out(hidio, "ok");
+                    }
+                }
+            } else if !hidio.tx_bytebuf.is_full() {
+                if let Some(event) = matrix_test::take_event() {
+                    let mut line: String<16> = String::new();
+                    let state = if event.closed { "down" } else { "up" };
+                    core::write!(line, "{} {} {}", state, event.row, event.col).ok();
+                    hidio_out(hidio, &line);
+                }
+            }
+            if let Some(usb_hid) = unsafe { USB_HID.as_mut() } {
+                usb_hid.push_hidio(hidio);
+            }
+        });
+        if bootloader::requested() {
+            reboot_to_bootloader(&mut delay);
+        }
+        key_stats::poll(millis());
+        #[cfg(feature = "split")]
+        split::hand::poll();
+        rgb::set_wpm(unsafe { WPM.wpm(millis()) });
+        let key_queue = unsafe { ACTIVE_QUEUE.get_keys() };
+        rgb::indicators::set_mods(
+            key_queue
+                .iter()
+                .flatten()
+                .filter_map(|k| k.modifier_bitmask())
+                .fold(0, |mods, bit| mods | bit),
+        );
+        let layer = layers::active();
+        rgb::indicators::set_layer(layer);
+        let ctx = Context {
+            key_queue,
+            host_leds: host_leds::get(),
+            layer,
+        };
+        matrix.poll(ctx);
+        #[cfg(feature = "encoders")]
+        encoders.poll(ctx);
+    }
+}
+
+/// tell the host every key is up and forget them
+fn release_all_keys() {
+    critical_section::with(|_| unsafe {
+        ACTIVE_QUEUE.clear();
+        RM_QUEUE.clear();
+        if let Some(kbd) = KBD_PRODUCER.get_mut().as_mut() {
+            kbd.enqueue(KeyState::Clear).ok();
+        }
+    });
+}
+
+/// release every key, give the host time to see it and jump into the bootloader
+fn reboot_to_bootloader(delay: &mut cortex_m::delay::Delay) -> ! {
+    info!("Releasing all keys and going into bootloader mode.");
+    key_stats::flush();
+    release_all_keys();
+    // keep pushing until the empty report and any HID-IO ack made it out
+    for _ in 0..50 {
+        critical_section::with(|_| unsafe {
+            if let Some(usb_hid) = USB_HID.as_mut() {
+                usb_hid.update();
+                usb_hid.push().ok();
+                if let Some(hidio) = HIDIO_INTF.get_mut().as_mut() {
+                    usb_hid.push_hidio(hidio);
+                }
+            }
         });
+        delay.delay_ms(2);
+    }
+    bootloader::enter();
+}
+
+/// add or remove the shift sent for shifted symbols(Sym_Excl etc.)
+/// the injected shift is only sent when the user isn't holding a shift key themselves
+/// and is dropped as soon as another key is pressed so it doesn't leak onto it
+/// called before a press makes it into ACTIVE_QUEUE
+fn shift_update(kbd: &mut Producer<'_, KeyState, KBD_QUEUE_SIZE>, code: KeyCode, pressed: bool) {
+    let keys = unsafe { ACTIVE_QUEUE.get_keys() };
+    let user_shift = keys
+        .iter()
+        .any(|k| matches!(k, Some(KeyCode::Mod_LSft | KeyCode::Mod_RSft)));
+    // a held key presses again on every poll, it is already in ACTIVE_QUEUE by then
+    let repeat = keys.contains(&Some(code));
+    let injected = SHIFT_INJECTED.load(Ordering::Relaxed);
+    let mut send = |state: KeyState| {
+        if let Err(err) = kbd.enqueue(state) {
+            error!("{}", err);
+        }
+    };
+    match (code, pressed) {
+        (KeyCode::Mod_LSft | KeyCode::Mod_RSft, true) => {
+            // the user's shift takes over from the injected one
+            if injected && code != KeyCode::Mod_LSft {
+                send(KeyState::Release(KeyCode::Mod_LSft.into()));
+            }
+            SHIFT_INJECTED.store(false, Ordering::Relaxed);
+        }
+        (KeyCode::Mod_LSft | KeyCode::Mod_RSft, false) => {
+            // a shifted symbol is still held so it still needs its shift
+            if any_shifted_held() && !user_shift {
+                send(KeyState::Press(KeyCode::Mod_LSft.into()));
+                SHIFT_INJECTED.store(true, Ordering::Relaxed);
+            }
+        }
+        // a held key presses again on every poll, only the first press counts
+        (code, true) if code.shifted_base().is_some() && shifted_held(code) => {}
+        (code, true) if code.shifted_base().is_some() => {
+            set_shifted_held(code, true);
+            if !user_shift && !injected {
+                send(KeyState::Press(KeyCode::Mod_LSft.into()));
+                SHIFT_INJECTED.store(true, Ordering::Relaxed);
+            }
+        }
+        (code, false) if code.shifted_base().is_some() => {
+            set_shifted_held(code, false);
+            if !any_shifted_held() && injected {
+                if !user_shift {
+                    send(KeyState::Release(KeyCode::Mod_LSft.into()));
+                }
+                SHIFT_INJECTED.store(false, Ordering::Relaxed);
+            }
+        }
+        (code, true) if injected && !code.is_modifier() && !repeat => {
+            send(KeyState::Release(KeyCode::Mod_LSft.into()));
+            SHIFT_INJECTED.store(false, Ordering::Relaxed);
+        }
+        _ => {}
+    }
+}
+
+/// whether the shifted symbol code is held, as counted by shift_update
+fn shifted_held(code: KeyCode) -> bool {
+    SHIFTED_HELD[code as usize / 32].load(Ordering::Relaxed) & 1 << (code as usize % 32) != 0
+}
+
+fn set_shifted_held(code: KeyCode, held: bool) {
+    let word = &SHIFTED_HELD[code as usize / 32];
+    let bit = 1 << (code as usize % 32);
+    let bits = word.load(Ordering::Relaxed);
+    word.store(
+        if held { bits | bit } else { bits & !bit },
+        Ordering::Relaxed,
+    );
+}
+
+fn any_shifted_held() -> bool {
+    SHIFTED_HELD.iter().any(|w| w.load(Ordering::Relaxed) != 0)
+}
+
+/// send a line of terminal output to the host over HID-IO
+fn hidio_out(hidio: &mut HidioCommandInterface, output: &str) {
+    if let Err(err) = hidio.h0034_terminalout(
+        h0034::Cmd {
+            output: String::from(output),
+        },
+        true,
+    ) {
+        error!("HID-IO output failed {}", err);
     }
 }
 
-static RCOL: AtomicU8 = AtomicU8::new(0);
-static GCOL: AtomicU8 = AtomicU8::new(0);
-static BCOL: AtomicU8 = AtomicU8::new(0);
+/// milliseconds since boot, wraps after ~49 days
+pub fn millis() -> u32 {
+    unsafe { TIMER.as_ref() }
+        .map(|t| (t.get_counter().ticks() / 1000) as u32)
+        .unwrap_or(0)
+}
+
+static LEDS_ENABLED: AtomicBool = AtomicBool::new(true);
+/// one bit per KeyCode, set while that shifted symbol is held
+/// a bit rather than a count so a release without a press(RM_QUEUE) can't throw it off
+static SHIFTED_HELD: [AtomicU32; 8] = [const { AtomicU32::new(0) }; 8];
+/// set while a shift the user isn't holding is being sent for a shifted symbol
+static SHIFT_INJECTED: AtomicBool = AtomicBool::new(false);
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub struct Context {
     key_queue: [Option<KeyCode>; 10],
+    /// lock LEDs(Caps, Num, Scroll...) as last set by the host
+    host_leds: HostLeds,
+    /// the active keymap layer, see layers
+    layer: u8,
 }
 
 static mut KBD_PRODUCER: Mutex<Option<Producer<'_, KeyState, KBD_QUEUE_SIZE>>> = Mutex::new(None);
+static mut TIMER: Option<Timer> = None;
 static mut USB_ALLOCATOR: Option<UsbBusAllocator<UsbBus>> = None;
 static mut HID_BUS: Option<UsbDevice<UsbBus>> = None;
 static mut USB_HID: Option<HidInterface> = None;
@@ -460,6 +938,8 @@ static mut REPORTSENT: AtomicBool = AtomicBool::new(false);
 static mut READYTOSEND: AtomicBool = AtomicBool::new(false);
 static mut ACTIVE_QUEUE: KeyQueue<10> = KeyQueue::new();
 static mut RM_QUEUE: KeyQueue<10> = KeyQueue::new();
+/// typing speed, fed by the matrix callback
+static mut WPM: Wpm = Wpm::new();
 // static mut STRING_QUEUE: KeyQueue<30> = KeyQueue::new();
 
 /// Handle USB interrupts, used by the host to "poll" the keyboard for new inputs.
@@ -468,12 +948,22 @@ static mut RM_QUEUE: KeyQueue<10> = KeyQueue::new();
 unsafe fn USBCTRL_IRQ() {
     if let Some(usb_dev) = HID_BUS.as_mut() {
         if let Some(usb_hid) = USB_HID.as_mut() {
-            if usb_dev.poll(&mut usb_hid.interfaces()) {
+            let polled = usb_dev.poll(&mut usb_hid.interfaces());
+            rgb::set_usb_state(usb_dev.state());
+            #[cfg(feature = "split")]
+            split::set_usb_state(usb_dev.state());
+            suspend::set_state(usb_dev.state(), usb_dev.remote_wakeup_enabled(), millis());
+            if polled {
                 usb_hid.pull();
+                // has to stay right after pull, see host_leds::drain
+                if let Some(leds) = KBD_LED_CONSUMER.as_mut() {
+                    host_leds::drain(leds);
+                }
                 let hidio_intf = critical_section::with(|_| HIDIO_INTF.get_mut().as_mut());
                 if hidio_intf.is_some() {
                     let hidio = hidio_intf.unwrap();
                     usb_hid.pull_hidio(hidio);
+                    usb_hid.push_hidio(hidio);
                 }
             }
         }
@@ -486,3 +976,75 @@ fn report_is_empty(report: &KeyboardNkroReport) -> bool {
         .iter()
         .any(|key| *key != key_codes::KeyCode::________ as u8)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        any_shifted_held, shift_update, KeyCode, KeyState, Operation, ACTIVE_QUEUE, KBD_QUEUE_SIZE,
+        SHIFT_INJECTED,
+    };
+    use core::sync::atomic::Ordering;
+    use heapless::spsc::Queue;
+    use std::sync::Mutex;
+
+    /// shift_update keeps its state in statics, the tests take turns
+    static SHIFT_STATE: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn held_shifted_symbol_releases_its_shift() {
+        let _state = SHIFT_STATE.lock().unwrap();
+        let mut queue: Queue<KeyState, KBD_QUEUE_SIZE> = Queue::new();
+        let (mut kbd, mut sent) = queue.split();
+        let shift = KeyState::Press(KeyCode::Mod_LSft.into());
+        // held for a few polls, each one presses again
+        for _ in 0..3 {
+            shift_update(&mut kbd, KeyCode::Sym_Excl, true);
+        }
+        assert_eq!(sent.dequeue(), Some(shift));
+        assert_eq!(sent.dequeue(), None);
+        shift_update(&mut kbd, KeyCode::Sym_Excl, false);
+        assert_eq!(
+            sent.dequeue(),
+            Some(KeyState::Release(KeyCode::Mod_LSft.into()))
+        );
+        assert!(!any_shifted_held());
+        assert!(!SHIFT_INJECTED.load(Ordering::Relaxed));
+    }
+
+    /// shift_update and then ACTIVE_QUEUE the way action() does it
+    fn press(kbd: &mut heapless::spsc::Producer<'_, KeyState, KBD_QUEUE_SIZE>, code: KeyCode) {
+        shift_update(kbd, code, true);
+        unsafe { ACTIVE_QUEUE.enqueue((code, Operation::SendOn)) };
+    }
+
+    fn release(kbd: &mut heapless::spsc::Producer<'_, KeyState, KBD_QUEUE_SIZE>, code: KeyCode) {
+        shift_update(kbd, code, false);
+        unsafe { ACTIVE_QUEUE.dequeue((code, Operation::SendOn)) };
+    }
+
+    #[test]
+    fn held_key_keeps_the_shift_of_a_shifted_symbol() {
+        let _state = SHIFT_STATE.lock().unwrap();
+        let mut queue: Queue<KeyState, KBD_QUEUE_SIZE> = Queue::new();
+        let (mut kbd, mut sent) = queue.split();
+        let shift = KeyCode::Mod_LSft.into();
+        // rolling from a onto !, a keeps pressing again every poll
+        press(&mut kbd, KeyCode::Ltr_Azzz);
+        press(&mut kbd, KeyCode::Sym_Excl);
+        assert_eq!(sent.dequeue(), Some(KeyState::Press(shift)));
+        for _ in 0..3 {
+            press(&mut kbd, KeyCode::Ltr_Azzz);
+            press(&mut kbd, KeyCode::Sym_Excl);
+        }
+        assert_eq!(sent.dequeue(), None);
+        // a new key still drops it
+        press(&mut kbd, KeyCode::Ltr_Bzzz);
+        assert_eq!(sent.dequeue(), Some(KeyState::Release(shift)));
+        for code in [KeyCode::Ltr_Azzz, KeyCode::Sym_Excl, KeyCode::Ltr_Bzzz] {
+            release(&mut kbd, code);
+        }
+        assert_eq!(sent.dequeue(), None);
+        assert!(!any_shifted_held());
+        assert!(!SHIFT_INJECTED.load(Ordering::Relaxed));
+    }
+}
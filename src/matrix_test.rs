@@ -0,0 +1,127 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use defmt::info;
+use heapless::Deque;
+use smart_leds::RGB8;
+
+use crate::rgb::led_map::nearest_led;
+use crate::rgb::{self, NUM_LEDS};
+use crate::scanner::ScanBits;
+use crate::{millis, MATRIX_COLS, MATRIX_ROWS};
+
+/// Switch events waiting to go out over HID-IO
+const EVENT_QUEUE_SIZE: usize = 16;
+/// LED colors for a region where a switch is held, every switch was seen and some weren't
+const HELD_COLOR: RGB8 = RGB8::new(255, 255, 255);
+const DONE_COLOR: RGB8 = RGB8::new(0, 255, 0);
+const TODO_COLOR: RGB8 = RGB8::new(255, 0, 0);
+
+/// Set while the matrix reports switches here instead of running the keymap
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// A switch opening or closing, zero indexed
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SwitchEvent {
+    pub row: u8,
+    pub col: u8,
+    pub closed: bool,
+}
+
+struct MatrixTest {
+    /// switches that closed at least once since the test started
+    seen: ScanBits<MATRIX_ROWS>,
+    /// switches closed right now
+    held: ScanBits<MATRIX_ROWS>,
+    events: Deque<SwitchEvent, EVENT_QUEUE_SIZE>,
+}
+
+// Only touched from the main loop(matrix poll and HID-IO)
+static mut TEST: MatrixTest = MatrixTest {
+    seen: ScanBits::new(),
+    held: ScanBits::new(),
+    events: Deque::new(),
+};
+
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// stop sending keys and report every switch until stop is called
+pub fn start() {
+    info!("Matrix test started, press every switch");
+    let test = unsafe { &mut TEST };
+    test.seen = ScanBits::new();
+    test.held = ScanBits::new();
+    test.events.clear();
+    ACTIVE.store(true, Ordering::Relaxed);
+    show();
+}
+
+/// go back to running the keymap
+pub fn stop() {
+    info!("Matrix test stopped, {} switches seen", seen());
+    ACTIVE.store(false, Ordering::Relaxed);
+    rgb::clear_frame();
+}
+
+/// switches that closed at least once since the test started
+pub fn seen() -> u32 {
+    unsafe { TEST.seen.rows.iter().map(|r| r.count_ones()).sum() }
+}
+
+/// the switch at row, col(zero indexed) opened or closed, called by the matrix during a test
+pub fn switch(row: usize, col: usize, closed: bool) {
+    let test = unsafe { &mut TEST };
+    info!(
+        "Matrix test row {} col {} {}",
+        row,
+        col,
+        if closed { "down" } else { "up" }
+    );
+    test.held.set(row, col, closed);
+    if closed {
+        test.seen.set(row, col, true);
+    }
+    let event = SwitchEvent {
+        row: row as u8,
+        col: col as u8,
+        closed,
+    };
+    // drop the oldest event rather than the newest if the host isn't reading
+    if test.events.is_full() {
+        test.events.pop_front();
+    }
+    test.events.push_back(event).ok();
+    // keeps the LEDs from fading out mid test
+    rgb::key_activity(millis());
+    show();
+}
+
+/// the oldest switch event that wasn't sent to the host yet
+pub fn take_event() -> Option<SwitchEvent> {
+    unsafe { TEST.events.pop_front() }
+}
+
+/// light each LED for the switches closest to it: white while one is held,
+/// green once all of them were seen and red until then
+fn show() {
+    let test = unsafe { &TEST };
+    let mut done = [true; NUM_LEDS];
+    let mut held = [false; NUM_LEDS];
+    for row in 0..MATRIX_ROWS {
+        for col in 0..MATRIX_COLS {
+            let led = nearest_led(row, col);
+            done[led] &= test.seen.get(row, col);
+            held[led] |= test.held.get(row, col);
+        }
+    }
+    let mut frame = [TODO_COLOR; NUM_LEDS];
+    for (i, color) in frame.iter_mut().enumerate() {
+        if held[i] {
+            *color = HELD_COLOR;
+        } else if done[i] {
+            *color = DONE_COLOR;
+        }
+    }
+    rgb::set_frame(frame);
+}
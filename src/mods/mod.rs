@@ -1,4 +1,4 @@
+pub mod mod_combo;
 pub mod mod_tap;
 pub mod mod_tapcom;
-pub mod mod_combo;
 pub mod rgb_key;
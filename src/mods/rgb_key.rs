@@ -9,7 +9,7 @@ use crate::ARGS;
 use crate::{key::Key, key_codes::KeyCode};
 
 pub trait RGBKey {
-    fn rknew(r: u8, g: u8, b: u8) -> Self
+    fn rknew(r: u8, g: u8, b: u8, target: Option<(u8, u8)>) -> Self
     where
         Self: Sized,
         Self: RGBKey;
@@ -22,7 +22,10 @@ pub trait RGBKey {
 }
 
 impl RGBKey for Key {
-    fn rknew(r: u8, g: u8, b: u8) -> Self {
+    /// target is the (row, col) of the key whose LED to color, None colors the whole strip
+    fn rknew(r: u8, g: u8, b: u8, target: Option<(u8, u8)>) -> Self {
+        // stor[3] marks a target, stor[4] and stor[5] hold it
+        let (row, col) = target.unwrap_or((0, 0));
         Key {
             cycles: 0,
             raw_state: false,
@@ -36,7 +39,7 @@ impl RGBKey for Key {
                 None,
             ],
             previnfo: [false; 6],
-            stor: [r, g, b, 0, 0, 0],
+            stor: [r, g, b, target.is_some() as u8, row, col],
             typ: "RGBKey",
         }
     }
@@ -44,14 +47,20 @@ impl RGBKey for Key {
         let [Some(_kc0), Some(kc1), None, None] = self.keycode else {
             return [None; 4];
         };
-        action(
-            CallbackActions::RGBSet,
-            ARGS::RGB {
-                r: self.stor[0],
-                g: self.stor[1],
-                b: self.stor[2],
-            },
-        );
+        let [r, g, b, targeted, row, col] = self.stor;
+        if targeted != 0 {
+            action(
+                CallbackActions::RGBKeySet,
+                ARGS::KeyRGB {
+                    key: (row, col),
+                    r,
+                    g,
+                    b,
+                },
+            );
+        } else {
+            action(CallbackActions::RGBSet, ARGS::RGB { r, g, b });
+        }
         [Some((kc1.0, kc1.1)), None, None, None]
     }
     fn rkhold(&mut self, _ctx: Context) -> [Option<(KeyCode, Operation)>; 4] {
@@ -0,0 +1,126 @@
+use core::cell::UnsafeCell;
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{fence, AtomicU32, Ordering};
+
+/// Latest value of T shared from one core to the other without locks
+/// The writer fills the slot the reader isn't pointed at and then flips to it,
+/// each slot has a sequence number(odd while being written) so a reader that
+/// raced a write retries instead of seeing half of each value
+/// Only uses atomic loads and stores since thumbv6m has nothing else
+pub struct DoubleBuffer<T> {
+    slots: [Slot<T>; 2],
+    /// bumped on every write, the low bit is the slot holding the newest value
+    version: AtomicU32,
+}
+
+struct Slot<T> {
+    seq: AtomicU32,
+    data: UnsafeCell<T>,
+}
+
+// SAFETY: reads are checked against the slot sequence numbers and there is a single writer
+unsafe impl<T: Copy + Send> Sync for DoubleBuffer<T> {}
+
+impl<T: Copy> DoubleBuffer<T> {
+    pub const fn new(val: T) -> Self {
+        DoubleBuffer {
+            slots: [
+                Slot {
+                    seq: AtomicU32::new(0),
+                    data: UnsafeCell::new(val),
+                },
+                Slot {
+                    seq: AtomicU32::new(0),
+                    data: UnsafeCell::new(val),
+                },
+            ],
+            version: AtomicU32::new(0),
+        }
+    }
+
+    /// publish a new value, must only ever be called from one core
+    pub fn write(&self, val: T) {
+        let version = self.version.load(Ordering::Relaxed).wrapping_add(1);
+        let slot = &self.slots[(version & 1) as usize];
+        let seq = slot.seq.load(Ordering::Relaxed);
+        slot.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+        unsafe { write_volatile(slot.data.get(), val) };
+        slot.seq.store(seq.wrapping_add(2), Ordering::Release);
+        self.version.store(version, Ordering::Release);
+    }
+
+    /// the newest value
+    pub fn read(&self) -> T {
+        loop {
+            let slot = &self.slots[(self.version() & 1) as usize];
+            let seq = slot.seq.load(Ordering::Acquire);
+            if seq & 1 == 1 {
+                continue;
+            }
+            let val = unsafe { read_volatile(slot.data.get()) };
+            fence(Ordering::Acquire);
+            if slot.seq.load(Ordering::Relaxed) == seq {
+                return val;
+            }
+        }
+    }
+
+    /// changes every time a new value is written
+    pub fn version(&self) -> u32 {
+        self.version.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+
+    use super::DoubleBuffer;
+
+    #[test]
+    fn read_gives_the_latest_write() {
+        let buf = DoubleBuffer::new(0u32);
+        assert_eq!(buf.read(), 0);
+        for val in 1..5 {
+            buf.write(val);
+            assert_eq!(buf.read(), val);
+        }
+    }
+
+    #[test]
+    fn version_changes_on_every_write() {
+        let buf = DoubleBuffer::new(0u32);
+        let mut version = buf.version();
+        // the same value again is still a new write
+        for val in [1, 1, 2] {
+            buf.write(val);
+            assert_ne!(buf.version(), version);
+            version = buf.version();
+        }
+    }
+
+    #[test]
+    fn racing_reader_never_sees_a_torn_value() {
+        let buf = DoubleBuffer::new([0u32; 16]);
+        let done = AtomicBool::new(false);
+        thread::scope(|s| {
+            s.spawn(|| {
+                for val in 1..=100_000 {
+                    buf.write([val; 16]);
+                }
+                done.store(true, Ordering::Release);
+            });
+            let mut last = 0;
+            while !done.load(Ordering::Acquire) {
+                let val = buf.read();
+                assert!(val.iter().all(|&v| v == val[0]), "torn read {:?}", val);
+                // the reader only ever moves forward
+                assert!(val[0] >= last);
+                last = val[0];
+            }
+            assert_eq!(buf.read(), [100_000; 16]);
+        });
+    }
+}
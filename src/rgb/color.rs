@@ -0,0 +1,182 @@
+use defmt::Format;
+use smart_leds::RGB8;
+
+/// Color as hue, saturation and value, all 0-255
+#[derive(Copy, Clone, PartialEq, Debug, Format)]
+pub struct Hsv {
+    pub h: u8,
+    pub s: u8,
+    pub v: u8,
+}
+
+impl Hsv {
+    pub const fn new(h: u8, s: u8, v: u8) -> Self {
+        Hsv { h, s, v }
+    }
+}
+
+impl From<Hsv> for RGB8 {
+    fn from(hsv: Hsv) -> Self {
+        let Hsv { h, s, v } = hsv;
+        if s == 0 {
+            return RGB8::new(v, v, v);
+        }
+        // six 43 wide regions around the wheel
+        let region = h / 43;
+        let rem = (h - region * 43) as u16 * 6;
+        let (s, v16) = (s as u16, v as u16);
+        let p = ((v16 * (255 - s)) >> 8) as u8;
+        let q = ((v16 * (255 - ((s * rem) >> 8))) >> 8) as u8;
+        let t = ((v16 * (255 - ((s * (255 - rem)) >> 8))) >> 8) as u8;
+        match region {
+            0 => RGB8::new(v, t, p),
+            1 => RGB8::new(q, v, p),
+            2 => RGB8::new(p, v, t),
+            3 => RGB8::new(p, q, v),
+            4 => RGB8::new(t, p, v),
+            _ => RGB8::new(v, p, q),
+        }
+    }
+}
+
+impl From<RGB8> for Hsv {
+    fn from(rgb: RGB8) -> Self {
+        let (r, g, b) = (rgb.r as i32, rgb.g as i32, rgb.b as i32);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        if max == 0 || delta == 0 {
+            return Hsv::new(0, 0, max as u8);
+        }
+        let s = (255 * delta / max) as u8;
+        let h = if max == r {
+            43 * (g - b) / delta
+        } else if max == g {
+            85 + 43 * (b - r) / delta
+        } else {
+            171 + 43 * (r - g) / delta
+        };
+        Hsv::new(h.rem_euclid(256) as u8, s, max as u8)
+    }
+}
+
+/// Perceived brightness to PWM duty, gamma 2.8
+#[rustfmt::skip]
+const GAMMA8: [u8; 256] = [
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   1,   1,   1,   1,
+      1,   1,   1,   1,   1,   1,   1,   1,   1,   2,   2,   2,   2,   2,   2,   2,
+      2,   3,   3,   3,   3,   3,   3,   3,   4,   4,   4,   4,   4,   5,   5,   5,
+      5,   6,   6,   6,   6,   7,   7,   7,   7,   8,   8,   8,   9,   9,   9,  10,
+     10,  10,  11,  11,  11,  12,  12,  13,  13,  13,  14,  14,  15,  15,  16,  16,
+     17,  17,  18,  18,  19,  19,  20,  20,  21,  21,  22,  22,  23,  24,  24,  25,
+     25,  26,  27,  27,  28,  29,  29,  30,  31,  32,  32,  33,  34,  35,  35,  36,
+     37,  38,  39,  39,  40,  41,  42,  43,  44,  45,  46,  47,  48,  49,  50,  50,
+     51,  52,  54,  55,  56,  57,  58,  59,  60,  61,  62,  63,  64,  66,  67,  68,
+     69,  70,  72,  73,  74,  75,  77,  78,  79,  81,  82,  83,  85,  86,  87,  89,
+     90,  92,  93,  95,  96,  98,  99, 101, 102, 104, 105, 107, 109, 110, 112, 114,
+    115, 117, 119, 120, 122, 124, 126, 127, 129, 131, 133, 135, 137, 138, 140, 142,
+    144, 146, 148, 150, 152, 154, 156, 158, 160, 162, 164, 167, 169, 171, 173, 175,
+    177, 180, 182, 184, 186, 189, 191, 193, 196, 198, 200, 203, 205, 208, 210, 213,
+    215, 218, 220, 223, 225, 228, 231, 233, 236, 239, 241, 244, 247, 249, 252, 255,
+];
+
+/// scale the frame by brightness and gamma correct it, the last step before the strip
+pub fn correct(frame: &mut [RGB8], brightness: u8) {
+    for led in frame.iter_mut() {
+        let c = scale(*led, brightness);
+        *led = RGB8::new(
+            GAMMA8[c.r as usize],
+            GAMMA8[c.g as usize],
+            GAMMA8[c.b as usize],
+        );
+    }
+}
+
+/// scale every channel by amount/255
+pub fn scale(color: RGB8, amount: u8) -> RGB8 {
+    let s = |c: u8| ((c as u16 * (amount as u16 + 1)) >> 8) as u8;
+    RGB8::new(s(color.r), s(color.g), s(color.b))
+}
+
+#[cfg(test)]
+mod tests {
+    use smart_leds::RGB8;
+
+    use super::{correct, scale, Hsv};
+
+    /// red, yellow, green, cyan, blue and magenta
+    const WHEEL: [(u8, u8, u8); 6] = [
+        (255, 0, 0),
+        (255, 255, 0),
+        (0, 255, 0),
+        (0, 255, 255),
+        (0, 0, 255),
+        (255, 0, 255),
+    ];
+
+    #[test]
+    fn primaries_and_secondaries_from_hsv() {
+        // the start of each region, the secondaries lose a step to the >> 8
+        let rgb = [
+            (255, 0, 0),
+            (254, 255, 0),
+            (0, 255, 0),
+            (0, 254, 255),
+            (0, 0, 255),
+            (255, 0, 254),
+        ];
+        for (region, (r, g, b)) in rgb.into_iter().enumerate() {
+            let hsv = Hsv::new(region as u8 * 43, 255, 255);
+            assert_eq!(RGB8::from(hsv), RGB8::new(r, g, b), "{:?}", hsv);
+        }
+    }
+
+    #[test]
+    fn primaries_and_secondaries_to_hsv() {
+        let hues = [0, 43, 85, 128, 171, 213];
+        for ((r, g, b), h) in WHEEL.into_iter().zip(hues) {
+            assert_eq!(Hsv::from(RGB8::new(r, g, b)), Hsv::new(h, 255, 255));
+        }
+    }
+
+    #[test]
+    fn no_saturation_is_grey() {
+        for h in [0, 100, 255] {
+            assert_eq!(RGB8::from(Hsv::new(h, 0, 77)), RGB8::new(77, 77, 77));
+        }
+        assert_eq!(Hsv::from(RGB8::new(77, 77, 77)), Hsv::new(0, 0, 77));
+        assert_eq!(Hsv::from(RGB8::new(0, 0, 0)), Hsv::new(0, 0, 0));
+    }
+
+    #[test]
+    fn scale_by_amount() {
+        let white = RGB8::new(255, 255, 255);
+        assert_eq!(scale(white, 0), RGB8::new(0, 0, 0));
+        assert_eq!(scale(white, 128), RGB8::new(128, 128, 128));
+        assert_eq!(scale(RGB8::new(10, 100, 200), 255), RGB8::new(10, 100, 200));
+    }
+
+    #[test]
+    fn correct_at_no_and_full_brightness() {
+        let colors = [
+            RGB8::new(255, 255, 255),
+            RGB8::new(128, 0, 255),
+            RGB8::new(1, 2, 3),
+        ];
+        let mut frame = colors;
+        correct(&mut frame, 0);
+        assert!(frame.iter().all(|&c| c == RGB8::new(0, 0, 0)));
+        // full brightness only applies the gamma
+        let mut frame = colors;
+        correct(&mut frame, 255);
+        assert_eq!(
+            frame,
+            [
+                RGB8::new(255, 255, 255),
+                RGB8::new(37, 0, 255),
+                RGB8::new(0, 0, 0),
+            ]
+        );
+    }
+}
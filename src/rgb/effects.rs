@@ -0,0 +1,266 @@
+use defmt::Format;
+use smart_leds::RGB8;
+
+use super::color::{scale, Hsv};
+use super::led_map::{distance, LedPos};
+
+/// Animations the LED engine can show
+#[derive(Copy, Clone, PartialEq, Debug, Format)]
+#[repr(u8)]
+pub enum Effect {
+    /// the color on every LED
+    Solid,
+    /// the color fading in and out
+    Breathing,
+    /// every LED cycling through the color wheel
+    Rainbow,
+    /// the hue shifting to the opposite side of the wheel across the board
+    Gradient,
+    /// a band of the color moving across the board
+    Wave,
+    /// a ring of the color rippling out from each pressed key and fading out
+    Reactive,
+    /// how often the keys near each LED get pressed, blue for rarely up to red for the most
+    Heatmap,
+}
+
+impl Effect {
+    pub const COUNT: u8 = 7;
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Effect::Solid => "solid",
+            Effect::Breathing => "breathing",
+            Effect::Rainbow => "rainbow",
+            Effect::Gradient => "gradient",
+            Effect::Wave => "wave",
+            Effect::Reactive => "reactive",
+            Effect::Heatmap => "heatmap",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        (0..Effect::COUNT)
+            .map(Effect::from)
+            .find(|e| e.name() == name)
+    }
+
+    /// the effect after this one, wrapping around
+    pub fn next(&self) -> Self {
+        Effect::from((*self as u8 + 1) % Effect::COUNT)
+    }
+
+    /// the effect before this one, wrapping around
+    pub fn prev(&self) -> Self {
+        Effect::from((*self as u8 + Effect::COUNT - 1) % Effect::COUNT)
+    }
+}
+
+impl From<u8> for Effect {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => Effect::Breathing,
+            2 => Effect::Rainbow,
+            3 => Effect::Gradient,
+            4 => Effect::Wave,
+            5 => Effect::Reactive,
+            6 => Effect::Heatmap,
+            _ => Effect::Solid,
+        }
+    }
+}
+
+/// Everything an effect needs to render a frame
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct EffectSettings {
+    pub effect: Effect,
+    pub color: Hsv,
+    /// 0 is stopped, 255 is the fastest
+    pub speed: u8,
+}
+
+/// The last key press, for Reactive
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct KeyPress {
+    /// time(ms) of the press
+    pub at: u32,
+    /// where the key is, see led_map::key_xy
+    pub x: u8,
+    pub y: u8,
+}
+
+/// Width of the Reactive ring, in led_map units
+const RING_WIDTH: u8 = 32;
+
+/// render a frame of the effect at time now(ms) into frame, map gives each LED's position
+/// and heat how often each LED's keys get pressed(0-255), see key_stats
+/// only depends on its arguments so it can be run anywhere
+pub fn render(
+    settings: &EffectSettings,
+    now: u32,
+    press: &KeyPress,
+    map: &[LedPos],
+    heat: &[u8],
+    frame: &mut [RGB8],
+) {
+    // advances 256 steps every ~2s at the default speed of 128
+    let phase = (now as u64 * settings.speed as u64 / 1024) as u8;
+    let hsv = settings.color;
+    let color = RGB8::from(hsv);
+    // phase steps since the last press
+    let since = (now.wrapping_sub(press.at) as u64 * settings.speed as u64 / 1024).min(255) as u8;
+    for (i, (led, pos)) in frame.iter_mut().zip(map).enumerate() {
+        *led = match settings.effect {
+            Effect::Solid => color,
+            Effect::Breathing => scale(color, sin8(phase)),
+            Effect::Rainbow => Hsv::new(phase.wrapping_add(pos.x), 255, 255).into(),
+            Effect::Gradient => Hsv::new(hsv.h.wrapping_add(pos.x / 2), hsv.s, hsv.v).into(),
+            Effect::Wave => scale(color, sin8(pos.x.wrapping_sub(phase))),
+            Effect::Reactive => {
+                // the ring grows one unit per phase step and fades out as it goes
+                let off = distance((pos.x, pos.y), (press.x, press.y)).abs_diff(since);
+                let ring = 255 - (off.min(RING_WIDTH) as u16 * 255 / RING_WIDTH as u16) as u8;
+                scale(scale(color, ring), 255 - since)
+            }
+            // hue 170(blue) for no presses down to 0(red) for the most
+            Effect::Heatmap => {
+                let heat = heat.get(i).copied().unwrap_or(0) as u16;
+                Hsv::new(170 - (heat * 170 / 255) as u8, 255, 255).into()
+            }
+        };
+    }
+}
+
+/// approximate sine wave, 0 -> 128, 64 -> 255, 128 -> 128, 192 -> 0
+pub fn sin8(x: u8) -> u8 {
+    // two parabolas standing in for the halves of the wave
+    let half = (x & 0x7f) as u16;
+    // peaks at 128 when half is 64, one more than the top half has room for
+    let y = (half * (128 - half) / 32) as u8;
+    if x < 128 {
+        128 + y.min(127)
+    } else {
+        128 - y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rgb::led_map::LED_MAP;
+    use crate::rgb::NUM_LEDS;
+
+    const RED: RGB8 = RGB8::new(255, 0, 0);
+    const BLACK: RGB8 = RGB8::new(0, 0, 0);
+
+    /// a frame of effect in full red at the default speed
+    fn frame(effect: Effect, now: u32, press: KeyPress, heat: &[u8]) -> [RGB8; NUM_LEDS] {
+        let settings = EffectSettings {
+            effect,
+            color: Hsv::new(0, 255, 255),
+            speed: 128,
+        };
+        let mut frame = [BLACK; NUM_LEDS];
+        render(&settings, now, &press, &LED_MAP, heat, &mut frame);
+        frame
+    }
+
+    #[test]
+    fn sin8_quarter_points() {
+        assert_eq!(sin8(0), 128);
+        assert_eq!(sin8(64), 255);
+        assert_eq!(sin8(128), 128);
+        assert_eq!(sin8(192), 0);
+    }
+
+    #[test]
+    fn solid() {
+        assert_eq!(
+            frame(Effect::Solid, 1234, KeyPress::default(), &[]),
+            [RED; NUM_LEDS]
+        );
+    }
+
+    #[test]
+    fn breathing() {
+        // phase is now / 8 at speed 128
+        let half = RGB8::new(128, 0, 0);
+        assert_eq!(
+            frame(Effect::Breathing, 0, KeyPress::default(), &[]),
+            [half; NUM_LEDS]
+        );
+        assert_eq!(
+            frame(Effect::Breathing, 512, KeyPress::default(), &[]),
+            [RED; NUM_LEDS]
+        );
+        assert_eq!(
+            frame(Effect::Breathing, 1536, KeyPress::default(), &[]),
+            [BLACK; NUM_LEDS]
+        );
+    }
+
+    #[test]
+    fn rainbow() {
+        let f = frame(Effect::Rainbow, 0, KeyPress::default(), &[]);
+        for (led, pos) in f.iter().zip(LED_MAP) {
+            assert_eq!(*led, RGB8::from(Hsv::new(pos.x, 255, 255)));
+        }
+        // a full turn of the wheel later it's back where it started
+        assert_eq!(frame(Effect::Rainbow, 2048, KeyPress::default(), &[]), f);
+    }
+
+    #[test]
+    fn gradient() {
+        let f = frame(Effect::Gradient, 0, KeyPress::default(), &[]);
+        assert_eq!(f[0], RED);
+        assert_eq!(f[NUM_LEDS - 1], RGB8::from(Hsv::new(127, 255, 255)));
+        // it doesn't move
+        assert_eq!(frame(Effect::Gradient, 777, KeyPress::default(), &[]), f);
+    }
+
+    #[test]
+    fn wave() {
+        // the crest is at x 64 + phase and the trough at x 192 + phase
+        let f = frame(Effect::Wave, 0, KeyPress::default(), &[]);
+        assert_eq!(f[0], RGB8::new(128, 0, 0));
+        // phase 45 puts the crest on LED 3 at x 109
+        let f = frame(Effect::Wave, 45 * 8, KeyPress::default(), &[]);
+        assert_eq!(f[3], RED);
+        let f = frame(Effect::Wave, 512, KeyPress::default(), &[]);
+        assert_eq!(f[0], BLACK);
+    }
+
+    #[test]
+    fn reactive() {
+        let press = KeyPress {
+            at: 1000,
+            x: 0,
+            y: 128,
+        };
+        // right after the press only the LED under it is lit
+        let f = frame(Effect::Reactive, 1000, press, &[]);
+        assert_eq!(f[0], RED);
+        assert!(f[2..].iter().all(|led| *led == BLACK));
+        // the ring has moved out to the next LED and faded some
+        let f = frame(Effect::Reactive, 1000 + 36 * 8, press, &[]);
+        assert_eq!(f[1].r, 219);
+        assert!(f[1].r > f[0].r);
+        // and is gone by the end
+        assert_eq!(
+            frame(Effect::Reactive, 1000 + 255 * 8, press, &[]),
+            [BLACK; NUM_LEDS]
+        );
+    }
+
+    #[test]
+    fn heatmap() {
+        let mut heat = [0; NUM_LEDS];
+        heat[0] = 255;
+        let f = frame(Effect::Heatmap, 0, KeyPress::default(), &heat);
+        assert_eq!(f[0], RED);
+        assert_eq!(f[1], RGB8::from(Hsv::new(170, 255, 255)));
+        // LEDs without heat count as never pressed
+        let f = frame(Effect::Heatmap, 0, KeyPress::default(), &[]);
+        assert_eq!(f, [RGB8::from(Hsv::new(170, 255, 255)); NUM_LEDS]);
+    }
+}
@@ -0,0 +1,64 @@
+use smart_leds::RGB8;
+
+use super::color::Hsv;
+use super::set_indicators;
+use crate::host_leds::HostLeds;
+
+/// Color shown on LAYER_LED for each layer, None leaves the base effect alone
+pub const LAYER_COLORS: [Option<Hsv>; 4] = [
+    None,
+    Some(Hsv::new(170, 255, 255)),
+    Some(Hsv::new(85, 255, 255)),
+    Some(Hsv::new(43, 255, 255)),
+];
+/// LED showing the active layer
+const LAYER_LED: usize = 0;
+/// (modifier bits, LED) lit while a modifier is held, bits as in KeyCode::modifier_bitmask
+/// left and right versions share an LED
+const MOD_LEDS: [(u8, usize); 4] = [
+    (0x11, 1), // Ctrl
+    (0x22, 2), // Shift
+    (0x44, 3), // Alt
+    (0x88, 4), // Cmd
+];
+const MOD_COLOR: Hsv = Hsv::new(0, 0, 255);
+/// Whole strip color while caps lock is on
+const CAPS_LOCK_COLOR: Hsv = Hsv::new(0, 255, 255);
+
+/// Keyboard state the indicators show
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct IndicatorState {
+    pub layer: u8,
+    /// held modifiers as a KeyCode::modifier_bitmask
+    pub mods: u8,
+}
+
+pub fn set_layer(layer: u8) {
+    set_indicators(|s| s.layer = layer);
+}
+
+pub fn set_mods(mods: u8) {
+    set_indicators(|s| s.mods = mods);
+}
+
+/// draw the indicators over a rendered frame
+/// caps lock covers the whole strip
+pub fn render(state: &IndicatorState, host_leds: HostLeds, frame: &mut [RGB8]) {
+    if host_leds.caps_lock() {
+        frame.fill(CAPS_LOCK_COLOR.into());
+        return;
+    }
+    let mut set = |led: usize, color: Hsv| {
+        if let Some(c) = frame.get_mut(led) {
+            *c = color.into();
+        }
+    };
+    if let Some(Some(color)) = LAYER_COLORS.get(state.layer as usize) {
+        set(LAYER_LED, *color);
+    }
+    for (bits, led) in MOD_LEDS {
+        if state.mods & bits != 0 {
+            set(led, MOD_COLOR);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use super::NUM_LEDS;
+use crate::{MATRIX_COLS, MATRIX_ROWS};
+
+/// Where an LED sits on the board, x and y run 0-255 from the top left
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct LedPos {
+    pub x: u8,
+    pub y: u8,
+}
+
+impl LedPos {
+    pub const fn new(x: u8, y: u8) -> Self {
+        LedPos { x, y }
+    }
+}
+
+/// Position of every LED in strip order
+/// The ErgoOne strip isn't tied to any switch so its LEDs are spread across the board,
+/// an LED under a switch goes at key_xy of that switch and is then nearest_led for it
+pub const LED_MAP: [LedPos; NUM_LEDS] = [
+    LedPos::new(0, 128),
+    LedPos::new(36, 128),
+    LedPos::new(73, 128),
+    LedPos::new(109, 128),
+    LedPos::new(146, 128),
+    LedPos::new(182, 128),
+    LedPos::new(219, 128),
+    LedPos::new(255, 128),
+];
+
+/// physical position of the switch at row, col(zero indexed), the matrix is a plain grid
+pub const fn key_xy(row: usize, col: usize) -> (u8, u8) {
+    (
+        (col * 255 / (MATRIX_COLS - 1)) as u8,
+        (row * 255 / (MATRIX_ROWS - 1)) as u8,
+    )
+}
+
+/// The LED closest to every switch, [row][col]
+const NEAREST_LED: [[u8; MATRIX_COLS]; MATRIX_ROWS] = {
+    let mut table = [[0; MATRIX_COLS]; MATRIX_ROWS];
+    let mut row = 0;
+    while row < MATRIX_ROWS {
+        let mut col = 0;
+        while col < MATRIX_COLS {
+            let pos = key_xy(row, col);
+            let mut nearest = 0;
+            let mut led = 1;
+            while led < NUM_LEDS {
+                let (a, b) = (LED_MAP[led], LED_MAP[nearest]);
+                if distance((a.x, a.y), pos) < distance((b.x, b.y), pos) {
+                    nearest = led;
+                }
+                led += 1;
+            }
+            table[row][col] = nearest as u8;
+            col += 1;
+        }
+        row += 1;
+    }
+    table
+};
+
+/// the LED closest to the switch at row, col(zero indexed)
+pub fn nearest_led(row: usize, col: usize) -> usize {
+    NEAREST_LED[row][col] as usize
+}
+
+/// rough distance between two points, max(dx, dy) + min(dx, dy) / 2 is within ~12% of the real one
+pub const fn distance(a: (u8, u8), b: (u8, u8)) -> u8 {
+    let dx = a.0.abs_diff(b.0);
+    let dy = a.1.abs_diff(b.1);
+    let (far, near) = if dx > dy { (dx, dy) } else { (dy, dx) };
+    far.saturating_add(near / 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_led_follows_the_column() {
+        assert_eq!(nearest_led(0, 0), 0);
+        assert_eq!(nearest_led(MATRIX_ROWS - 1, 0), 0);
+        assert_eq!(nearest_led(0, MATRIX_COLS - 1), NUM_LEDS - 1);
+        assert_eq!(nearest_led(MATRIX_ROWS - 1, MATRIX_COLS / 2), NUM_LEDS / 2);
+    }
+}
@@ -0,0 +1,344 @@
+pub mod channel;
+pub mod color;
+pub mod effects;
+pub mod indicators;
+pub mod led_map;
+pub mod power;
+
+use core::sync::atomic::{AtomicU16, AtomicU32, Ordering};
+
+use defmt::info;
+use smart_leds::RGB8;
+use usb_device::prelude::UsbDeviceState;
+
+use crate::key_codes::KeyCode;
+use crate::{host_leds, suspend, MATRIX_COLS, MATRIX_ROWS};
+use channel::DoubleBuffer;
+pub use color::Hsv;
+pub use effects::{Effect, EffectSettings, KeyPress};
+pub use indicators::IndicatorState;
+use led_map::LED_MAP;
+
+/// Number of WS2812 LEDs on the strip
+pub const NUM_LEDS: usize = 8;
+/// Time(ms) between frames of an animation, ~60fps
+pub const FRAME_MS: u32 = 16;
+/// How much the speed/brightness/hsv keys change the setting per press
+const STEP: u8 = 16;
+/// Time(ms) the strip takes to fade all the way out or back in
+const FADE_MS: u32 = 1000;
+
+/// Everything core 1 needs to draw the strip, published by core 0 as one snapshot
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct LedConfig {
+    pub settings: EffectSettings,
+    /// cap applied to every LED after the effect and indicators, dims the whole strip
+    pub brightness: u8,
+    pub indicators: IndicatorState,
+    /// seconds without key activity before the strip fades out, 0 keeps it on
+    pub idle_timeout: u16,
+    /// colors set for single keys by RGBKey, drawn over the effect
+    pub key_colors: [Option<RGB8>; NUM_LEDS],
+    /// the effect speed follows the typing speed instead of settings.speed
+    pub speed_wpm: bool,
+}
+
+impl LedConfig {
+    const DEFAULT: LedConfig = LedConfig {
+        settings: EffectSettings {
+            effect: Effect::Solid,
+            color: Hsv::new(0, 0, 0),
+            speed: 128,
+        },
+        brightness: 255,
+        indicators: IndicatorState { layer: 0, mods: 0 },
+        idle_timeout: 300,
+        key_colors: [None; NUM_LEDS],
+        speed_wpm: false,
+    };
+}
+
+// Written by core 0(keys, HID-IO) and read by core 1
+static CONFIG: DoubleBuffer<LedConfig> = DoubleBuffer::new(LedConfig::DEFAULT);
+/// A whole frame from the host, shown instead of the effect until an effect setting changes
+static FRAME: DoubleBuffer<Option<[RGB8; NUM_LEDS]>> = DoubleBuffer::new(None);
+/// The last key press, for the reactive effect
+static LAST_PRESS: DoubleBuffer<KeyPress> = DoubleBuffer::new(KeyPress { at: 0, x: 0, y: 0 });
+/// How often the keys near each LED get pressed, for the heatmap effect
+static HEAT: DoubleBuffer<[u8; NUM_LEDS]> = DoubleBuffer::new([0; NUM_LEDS]);
+/// Words per minute being typed, for speed_wpm
+static WPM: AtomicU16 = AtomicU16::new(0);
+/// Time(ms) of the last key event the matrix reported, for the idle timeout
+static LAST_ACTIVITY: AtomicU32 = AtomicU32::new(0);
+/// Current(mA) the LEDs may draw, follows the USB device state
+static POWER_BUDGET: AtomicU32 = AtomicU32::new(power::BUDGET_UNCONFIGURED_MA);
+
+/// change the config and hand it to core 1, only called from core 0
+fn update(f: impl FnOnce(&mut LedConfig)) {
+    let old = CONFIG.read();
+    let mut config = old;
+    f(&mut config);
+    if config != old {
+        CONFIG.write(config);
+    }
+}
+
+/// change an effect setting, this also drops any frame the host sent
+fn update_effect(f: impl FnOnce(&mut LedConfig)) {
+    update(f);
+    clear_frame();
+}
+
+pub fn set_color(r: u8, g: u8, b: u8) {
+    set_hsv(RGB8::new(r, g, b).into());
+}
+
+pub fn set_hsv(hsv: Hsv) {
+    update_effect(|c| c.settings.color = hsv);
+}
+
+pub fn set_effect(effect: Effect) {
+    info!("LED effect {}", effect);
+    update_effect(|c| c.settings.effect = effect);
+}
+
+pub fn set_speed(speed: u8) {
+    update_effect(|c| {
+        c.settings.speed = speed;
+        c.speed_wpm = false;
+    });
+}
+
+/// make the effect speed follow the typing speed, until the next set_speed
+pub fn set_speed_wpm() {
+    update_effect(|c| c.speed_wpm = true);
+}
+
+/// the current typing speed, see wpm::Wpm
+pub fn set_wpm(wpm: u16) {
+    WPM.store(wpm, Ordering::Relaxed);
+}
+
+/// effect speed for a typing speed, slow drifting when idle up to the fastest at 120wpm
+fn wpm_speed(wpm: u16) -> u8 {
+    (32 + wpm as u32 * 223 / 120).min(255) as u8
+}
+
+pub fn set_brightness(brightness: u8) {
+    update(|c| c.brightness = brightness);
+}
+
+/// the settings core 1 draws with, speed_wpm already applied
+#[cfg(feature = "split")]
+pub fn effect_settings() -> EffectSettings {
+    let config = CONFIG.read();
+    let mut settings = config.settings;
+    if config.speed_wpm {
+        settings.speed = wpm_speed(WPM.load(Ordering::Relaxed));
+    }
+    settings
+}
+
+#[cfg(feature = "split")]
+pub fn config() -> LedConfig {
+    CONFIG.read()
+}
+
+/// take over the lighting of another board, e.g. the primary half of a split
+#[cfg(feature = "split")]
+pub fn set_shared(settings: EffectSettings, brightness: u8, indicators: IndicatorState) {
+    update(|c| {
+        c.settings = settings;
+        c.brightness = brightness;
+        c.indicators = indicators;
+        c.speed_wpm = false;
+    });
+}
+
+/// show a whole frame from the host instead of the effect
+pub fn set_frame(frame: [RGB8; NUM_LEDS]) {
+    FRAME.write(Some(frame));
+}
+
+/// go back to the effect after set_frame
+pub fn clear_frame() {
+    if FRAME.read().is_some() {
+        FRAME.write(None);
+    }
+}
+
+/// seconds without key activity before the strip fades out, 0 keeps it on
+pub fn set_idle_timeout(seconds: u16) {
+    update(|c| c.idle_timeout = seconds);
+}
+
+/// update the heatmap, heat(0-255) for each LED
+pub fn set_heat(heat: [u8; NUM_LEDS]) {
+    if HEAT.read() != heat {
+        HEAT.write(heat);
+    }
+}
+
+/// toggle the LED nearest the key at row, col(zero indexed) between color and the effect
+/// returns false if there is no such key
+pub fn toggle_key_color(row: usize, col: usize, color: RGB8) -> bool {
+    if row >= MATRIX_ROWS || col >= MATRIX_COLS {
+        return false;
+    }
+    let led = led_map::nearest_led(row, col);
+    update(|c| {
+        c.key_colors[led] = match c.key_colors[led] {
+            Some(old) if old == color => None,
+            _ => Some(color),
+        }
+    });
+    true
+}
+
+/// change what the indicators show, only called from core 0
+pub fn set_indicators(f: impl FnOnce(&mut IndicatorState)) {
+    update(|c| f(&mut c.indicators));
+}
+
+/// pick the LED power budget for the state the USB device is in
+/// only called from the USB interrupt
+pub fn set_usb_state(state: UsbDeviceState) {
+    let budget = power::budget_for(state);
+    if POWER_BUDGET.load(Ordering::Relaxed) != budget {
+        POWER_BUDGET.store(budget, Ordering::Relaxed);
+        info!("LED power budget {}mA({})", budget, state);
+    }
+}
+
+/// note a key event from the matrix, restarts the idle timeout
+pub fn key_activity(now: u32) {
+    LAST_ACTIVITY.store(now, Ordering::Relaxed);
+}
+
+/// note a key press at row, col(zero indexed) for the reactive effect
+pub fn key_pressed(now: u32, row: usize, col: usize) {
+    let (x, y) = led_map::key_xy(row, col);
+    LAST_PRESS.write(KeyPress { at: now, x, y });
+}
+
+/// handle one of the Led_ keycodes, returns false if it isn't one
+pub fn handle_key(code: KeyCode) -> bool {
+    let LedConfig {
+        settings:
+            EffectSettings {
+                effect,
+                color,
+                speed,
+            },
+        brightness,
+        ..
+    } = CONFIG.read();
+    let Hsv { h, s, v } = color;
+    match code {
+        KeyCode::Led_Next => set_effect(effect.next()),
+        KeyCode::Led_Prev => set_effect(effect.prev()),
+        KeyCode::Led_SpUp => set_speed(speed.saturating_add(STEP)),
+        KeyCode::Led_SpDn => set_speed(speed.saturating_sub(STEP)),
+        KeyCode::Led_BrUp => set_brightness(brightness.saturating_add(STEP)),
+        KeyCode::Led_BrDn => set_brightness(brightness.saturating_sub(STEP)),
+        // hue goes around the wheel, saturation and value stop at the ends
+        KeyCode::Led_HueU => set_hsv(Hsv::new(h.wrapping_add(STEP), s, v)),
+        KeyCode::Led_HueD => set_hsv(Hsv::new(h.wrapping_sub(STEP), s, v)),
+        KeyCode::Led_SatU => set_hsv(Hsv::new(h, s.saturating_add(STEP), v)),
+        KeyCode::Led_SatD => set_hsv(Hsv::new(h, s.saturating_sub(STEP), v)),
+        KeyCode::Led_ValU => set_hsv(Hsv::new(h, s, v.saturating_add(STEP))),
+        KeyCode::Led_ValD => set_hsv(Hsv::new(h, s, v.saturating_sub(STEP))),
+        _ => return false,
+    }
+    true
+}
+
+/// Core 1 side, works out when the strip needs writing and fades it with the idle timeout
+pub struct Refresh {
+    /// (config, frame and heat versions, enabled, caps lock, power budget, awake)
+    /// at the last refresh
+    inputs: Option<([u32; 3], bool, bool, u32, bool)>,
+    last: u32,
+    /// how far the strip is faded in, 0 is off
+    level: u8,
+}
+
+impl Refresh {
+    pub const fn new() -> Self {
+        Refresh {
+            inputs: None,
+            last: 0,
+            level: 255,
+        }
+    }
+
+    /// true when anything the strip shows changed or the next animation frame is due at now(ms)
+    pub fn due(&mut self, now: u32, enabled: bool) -> bool {
+        let config = CONFIG.read();
+        let suspended = suspend::is_suspended();
+        let idle = config.idle_timeout != 0
+            && now.wrapping_sub(LAST_ACTIVITY.load(Ordering::Relaxed))
+                >= config.idle_timeout as u32 * 1000;
+        let awake = !suspended && !idle;
+        let inputs = Some((
+            [CONFIG.version(), FRAME.version(), HEAT.version()],
+            enabled,
+            host_leds::get().caps_lock(),
+            POWER_BUDGET.load(Ordering::Relaxed),
+            awake,
+        ));
+        let target = if awake { 255 } else { 0 };
+        // a solid color, the heatmap or a frame from the host stays put
+        let moving = !matches!(config.settings.effect, Effect::Solid | Effect::Heatmap);
+        let animated = enabled
+            && (self.level != target || (self.level != 0 && moving && FRAME.read().is_none()));
+        let elapsed = now.wrapping_sub(self.last);
+        if inputs == self.inputs && !(animated && elapsed >= FRAME_MS) {
+            return false;
+        }
+        // the bus may only supply standby current while suspended, so that goes dark at once
+        // a fade starting after a long quiet spell still only moves one frame's worth
+        let step = (elapsed.min(FRAME_MS) * 255 / FADE_MS).max(1) as u8;
+        self.level = match (suspended, awake) {
+            (true, _) => 0,
+            (_, true) => self.level.saturating_add(step),
+            (_, false) => self.level.saturating_sub(step),
+        };
+        self.inputs = inputs;
+        self.last = now;
+        true
+    }
+
+    /// draw the strip at time now(ms), the effect with any key colors or the host frame
+    /// with the indicators on top, then the brightness cap and fade, gamma correction
+    /// and power limit
+    pub fn draw(&self, now: u32, frame: &mut [RGB8; NUM_LEDS]) {
+        let config = CONFIG.read();
+        match FRAME.read() {
+            Some(host_frame) => *frame = host_frame,
+            None => {
+                let mut settings = config.settings;
+                if config.speed_wpm {
+                    settings.speed = wpm_speed(WPM.load(Ordering::Relaxed));
+                }
+                effects::render(
+                    &settings,
+                    now,
+                    &LAST_PRESS.read(),
+                    &LED_MAP,
+                    &HEAT.read(),
+                    frame,
+                );
+                for (led, color) in frame.iter_mut().zip(config.key_colors) {
+                    if let Some(color) = color {
+                        *led = color;
+                    }
+                }
+            }
+        }
+        indicators::render(&config.indicators, host_leds::get(), frame);
+        let brightness = (config.brightness as u16 * self.level as u16 / 255) as u8;
+        color::correct(frame, brightness);
+        power::limit(frame, POWER_BUDGET.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,86 @@
+use smart_leds::RGB8;
+use usb_device::prelude::UsbDeviceState;
+
+/// Current(mA) the whole keyboard asks the host for, see UsbDeviceBuilder::max_power
+pub const USB_MAX_POWER_MA: usize = 500;
+/// Current(mA) left for the LEDs once the host has configured us
+pub const BUDGET_CONFIGURED_MA: u32 = 400;
+/// Before configuration the whole device may only draw 100mA
+pub const BUDGET_UNCONFIGURED_MA: u32 = 40;
+/// While suspended the device may only draw 2.5mA, so the LEDs go dark
+pub const BUDGET_SUSPENDED_MA: u32 = 0;
+
+/// Current(mA) a WS2812 channel draws at full duty
+const MA_PER_CHANNEL: u32 = 20;
+/// Current(mA) a WS2812 draws with every channel off
+const MA_IDLE_PER_LED: u32 = 1;
+
+/// LED budget(mA) for the state the USB device is in
+pub fn budget_for(state: UsbDeviceState) -> u32 {
+    match state {
+        UsbDeviceState::Configured => BUDGET_CONFIGURED_MA,
+        UsbDeviceState::Default | UsbDeviceState::Addressed => BUDGET_UNCONFIGURED_MA,
+        UsbDeviceState::Suspend => BUDGET_SUSPENDED_MA,
+    }
+}
+
+/// sum of every channel's duty(0-255) in frame
+fn duty(frame: &[RGB8]) -> u32 {
+    frame
+        .iter()
+        .map(|c| c.r as u32 + c.g as u32 + c.b as u32)
+        .sum()
+}
+
+/// scale frame down until it fits in budget_ma
+pub fn limit(frame: &mut [RGB8], budget_ma: u32) {
+    let idle = frame.len() as u32 * MA_IDLE_PER_LED;
+    let available = budget_ma.saturating_sub(idle) * 255 / MA_PER_CHANNEL;
+    let draw = duty(frame);
+    if draw <= available {
+        return;
+    }
+    // rounding every channel down keeps the total at or under the budget
+    let s = |c: u8| (c as u32 * available / draw) as u8;
+    frame
+        .iter_mut()
+        .for_each(|c| *c = RGB8::new(s(c.r), s(c.g), s(c.b)));
+}
+
+#[cfg(test)]
+mod tests {
+    use smart_leds::RGB8;
+
+    use super::{duty, limit, BUDGET_CONFIGURED_MA, MA_IDLE_PER_LED, MA_PER_CHANNEL};
+
+    /// current(mA) the frame draws
+    fn draw_ma(frame: &[RGB8]) -> u32 {
+        frame.len() as u32 * MA_IDLE_PER_LED + duty(frame) * MA_PER_CHANNEL / 255
+    }
+
+    #[test]
+    fn full_white_is_scaled_under_the_budget() {
+        let mut frame = [RGB8::new(255, 255, 255); 80];
+        assert!(draw_ma(&frame) > BUDGET_CONFIGURED_MA);
+        limit(&mut frame, BUDGET_CONFIGURED_MA);
+        assert!(draw_ma(&frame) <= BUDGET_CONFIGURED_MA);
+        // scaled down, not turned off
+        assert!(frame.iter().all(|&c| c == frame[0] && c.r > 0));
+    }
+
+    #[test]
+    fn no_budget_is_black() {
+        let mut frame = [RGB8::new(255, 128, 1); 80];
+        limit(&mut frame, 0);
+        assert!(frame.iter().all(|&c| c == RGB8::new(0, 0, 0)));
+    }
+
+    #[test]
+    fn under_the_budget_is_unchanged() {
+        let colors = [RGB8::new(5, 10, 15); 80];
+        let mut frame = colors;
+        assert!(draw_ma(&frame) <= BUDGET_CONFIGURED_MA);
+        limit(&mut frame, BUDGET_CONFIGURED_MA);
+        assert_eq!(frame, colors);
+    }
+}
@@ -0,0 +1,179 @@
+use defmt::{debug, warn};
+
+use super::{ScanBits, Scanner};
+use crate::key::DEBOUNCE_CYCLES;
+use crate::{MATRIX_COLS, MATRIX_ROWS};
+
+/// A press this soon(ms) after the switch opened is counted as chatter
+const CHATTER_MS: u32 = 30;
+/// Shorter gaps(ms) are plain bounce that the key debounce hides, a cycle is a 1ms poll
+const BOUNCE_MS: u32 = DEBOUNCE_CYCLES as u32;
+/// Chatter count that flags a switch as worn
+const FLAG_COUNT: u16 = 5;
+
+/// Chatter seen on every matrix position since boot(or the last clear)
+// Only touched from the main loop(matrix poll and HID-IO)
+static mut COUNTS: [[u16; MATRIX_COLS]; MATRIX_ROWS] = [[0; MATRIX_COLS]; MATRIX_ROWS];
+
+/// times the switch at row, col(zero indexed) chattered
+pub fn count(row: usize, col: usize) -> u16 {
+    unsafe { COUNTS.get(row).and_then(|r| r.get(col)).copied() }.unwrap_or(0)
+}
+
+/// the switch at row, col(zero indexed) chattered often enough to count as worn
+pub fn is_flagged(row: usize, col: usize) -> bool {
+    count(row, col) >= FLAG_COUNT
+}
+
+/// forget all chatter, e.g. after a switch was replaced
+pub fn clear() {
+    unsafe { COUNTS = [[0; MATRIX_COLS]; MATRIX_ROWS] };
+}
+
+/// Wraps a scanner and watches every switch for chatter
+/// A switch that closes again CHATTER_MS after it opened gets counted and after FLAG_COUNT
+/// of those it is flagged as worn, with debounce set flagged switches also only pass a
+/// release on once they stayed open for CHATTER_MS so their double taps go away
+pub struct ChatterFilter<S: Scanner<MATRIX_ROWS>> {
+    scanner: S,
+    /// current time(ms)
+    now: fn() -> u32,
+    /// hold back releases of flagged switches
+    debounce: bool,
+    /// the raw switch states of the last scan
+    raw: ScanBits<MATRIX_ROWS>,
+    /// time(ms) each switch opened, None until it was seen opening
+    opened_at: [[Option<u32>; MATRIX_COLS]; MATRIX_ROWS],
+}
+
+impl<S: Scanner<MATRIX_ROWS>> ChatterFilter<S> {
+    pub const fn new(scanner: S, now: fn() -> u32, debounce: bool) -> Self {
+        ChatterFilter {
+            scanner,
+            now,
+            debounce,
+            raw: ScanBits::new(),
+            opened_at: [[None; MATRIX_COLS]; MATRIX_ROWS],
+        }
+    }
+}
+
+impl<S: Scanner<MATRIX_ROWS>> Scanner<MATRIX_ROWS> for ChatterFilter<S> {
+    fn scan(&mut self) -> ScanBits<MATRIX_ROWS> {
+        let raw = self.scanner.scan();
+        let now = (self.now)();
+        for (r, c) in raw.changed(&self.raw) {
+            let Some(opened_at) = self.opened_at.get_mut(r).and_then(|row| row.get_mut(c)) else {
+                continue;
+            };
+            if !raw.get(r, c) {
+                *opened_at = Some(now);
+                continue;
+            }
+            let Some(gap) = opened_at.map(|at| now.wrapping_sub(at)) else {
+                continue;
+            };
+            if (BOUNCE_MS..CHATTER_MS).contains(&gap) {
+                let count = unsafe { &mut COUNTS[r][c] };
+                *count = count.saturating_add(1);
+                debug!(
+                    "Switch {} {} chattered, closed {}ms after opening",
+                    r, c, gap
+                );
+                if *count == FLAG_COUNT {
+                    warn!("Switch {} {} is chattering, it may be worn", r, c);
+                }
+            }
+        }
+        self.raw = raw;
+
+        let mut bits = raw;
+        if self.debounce {
+            for (r, row) in self.opened_at.iter().enumerate() {
+                for (c, opened_at) in row.iter().enumerate() {
+                    let held = opened_at.is_some_and(|at| now.wrapping_sub(at) < CHATTER_MS);
+                    if held && !raw.get(r, c) && is_flagged(r, c) {
+                        bits.set(r, c, true);
+                    }
+                }
+            }
+        }
+        bits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::FakeScanner;
+    use std::cell::Cell;
+    use std::sync::{Mutex, MutexGuard};
+
+    std::thread_local! {
+        /// time(ms) of the test running on this thread
+        static NOW: Cell<u32> = const { Cell::new(0) };
+    }
+
+    fn now() -> u32 {
+        NOW.get()
+    }
+
+    /// COUNTS is shared, the tests take turns and start from no chatter
+    static COUNTS_LOCK: Mutex<()> = Mutex::new(());
+
+    fn cleared_counts() -> MutexGuard<'static, ()> {
+        let guard = COUNTS_LOCK.lock().unwrap();
+        clear();
+        guard
+    }
+
+    /// set the switch at row, col and scan at time at(ms)
+    fn scan_at(
+        filter: &mut ChatterFilter<FakeScanner<MATRIX_ROWS>>,
+        at: u32,
+        row: usize,
+        col: usize,
+        closed: bool,
+    ) -> bool {
+        filter.scanner.bits.set(row, col, closed);
+        NOW.set(at);
+        filter.scan().get(row, col)
+    }
+
+    #[test]
+    fn counts_chatter_but_not_bounce() {
+        let _counts = cleared_counts();
+        let mut filter = ChatterFilter::new(FakeScanner::<MATRIX_ROWS>::new(), now, false);
+        scan_at(&mut filter, 0, 0, 0, true);
+        scan_at(&mut filter, 100, 0, 0, false);
+        // closed again within the key debounce, plain bounce
+        scan_at(&mut filter, 102, 0, 0, true);
+        assert_eq!(count(0, 0), 0);
+        scan_at(&mut filter, 200, 0, 0, false);
+        scan_at(&mut filter, 210, 0, 0, true);
+        assert_eq!(count(0, 0), 1);
+        // a slow double tap isn't chatter
+        scan_at(&mut filter, 300, 0, 0, false);
+        scan_at(&mut filter, 400, 0, 0, true);
+        assert_eq!(count(0, 0), 1);
+    }
+
+    #[test]
+    fn flagged_switch_gets_held_on_release() {
+        let _counts = cleared_counts();
+        let mut filter = ChatterFilter::new(FakeScanner::<MATRIX_ROWS>::new(), now, true);
+        let mut t = 0;
+        for _ in 0..FLAG_COUNT {
+            scan_at(&mut filter, t, 1, 1, true);
+            scan_at(&mut filter, t + 100, 1, 1, false);
+            t += 110;
+        }
+        // before it's flagged a release goes through at once
+        assert!(!scan_at(&mut filter, t - 10, 1, 1, false));
+        scan_at(&mut filter, t, 1, 1, true);
+        assert!(is_flagged(1, 1));
+        assert!(scan_at(&mut filter, t + 100, 1, 1, false));
+        assert!(scan_at(&mut filter, t + 100 + CHATTER_MS - 1, 1, 1, false));
+        assert!(!scan_at(&mut filter, t + 100 + CHATTER_MS, 1, 1, false));
+    }
+}
@@ -0,0 +1,36 @@
+use embedded_hal::digital::v2::InputPin;
+use rp2040_hal::gpio::DynPin;
+
+use super::{ScanBits, Scanner, Sense};
+
+/// Reads a matrix where every switch has a GPIO of its own, e.g. a macropad
+/// None marks positions without a switch
+pub struct DirectScanner<const RSIZE: usize, const CSIZE: usize> {
+    pins: [[Option<DynPin>; CSIZE]; RSIZE],
+    sense: Sense,
+}
+
+impl<const RSIZE: usize, const CSIZE: usize> DirectScanner<RSIZE, CSIZE> {
+    /// sense is how the pins rest while their switch is open, usually PullUp with the
+    /// switches going to ground
+    pub fn new(mut pins: [[Option<DynPin>; CSIZE]; RSIZE], sense: Sense) -> Self {
+        for pin in pins.iter_mut().flatten().flatten() {
+            sense.into_input(pin);
+        }
+        DirectScanner { pins, sense }
+    }
+}
+
+impl<const RSIZE: usize, const CSIZE: usize> Scanner<RSIZE> for DirectScanner<RSIZE, CSIZE> {
+    fn scan(&mut self) -> ScanBits<RSIZE> {
+        let mut bits = ScanBits::new();
+        for (r, row) in self.pins.iter_mut().enumerate() {
+            for (c, pin) in row.iter_mut().enumerate() {
+                if let Some(pin) = pin {
+                    bits.set(r, c, pin.is_high().unwrap() == self.sense.active_high());
+                }
+            }
+        }
+        bits
+    }
+}
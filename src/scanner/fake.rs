@@ -0,0 +1,29 @@
+use super::{ScanBits, Scanner};
+
+/// Scanner that reports whatever switches it was told are closed, for running the key
+/// logic without a board
+pub struct FakeScanner<const RSIZE: usize> {
+    pub bits: ScanBits<RSIZE>,
+}
+
+impl<const RSIZE: usize> FakeScanner<RSIZE> {
+    pub const fn new() -> Self {
+        FakeScanner {
+            bits: ScanBits::new(),
+        }
+    }
+    /// close the switch at row, col(zero indexed)
+    pub fn press(&mut self, row: usize, col: usize) {
+        self.bits.set(row, col, true);
+    }
+    /// open the switch at row, col(zero indexed)
+    pub fn release(&mut self, row: usize, col: usize) {
+        self.bits.set(row, col, false);
+    }
+}
+
+impl<const RSIZE: usize> Scanner<RSIZE> for FakeScanner<RSIZE> {
+    fn scan(&mut self) -> ScanBits<RSIZE> {
+        self.bits
+    }
+}
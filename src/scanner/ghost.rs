@@ -0,0 +1,97 @@
+use defmt::warn;
+
+use super::{ScanBits, Scanner};
+
+/// Wraps a scanner and holds back switches that could be ghosts
+/// While a rectangle of closed switches is seen its corners keep the state they had before
+/// it appeared, so keys already down stay down and no phantom press is sent
+/// Only worth it without diodes(or with a failed one), with diodes a rectangle is four real
+/// presses and holding them back would eat chords
+pub struct GhostFilter<const RSIZE: usize, S: Scanner<RSIZE>> {
+    scanner: S,
+    /// pass the scans through untouched when false
+    enabled: bool,
+    /// what was reported last
+    bits: ScanBits<RSIZE>,
+    /// positions held back in the last scan
+    ghosts: ScanBits<RSIZE>,
+}
+
+impl<const RSIZE: usize, S: Scanner<RSIZE>> GhostFilter<RSIZE, S> {
+    pub const fn new(scanner: S, enabled: bool) -> Self {
+        GhostFilter {
+            scanner,
+            enabled,
+            bits: ScanBits::new(),
+            ghosts: ScanBits::new(),
+        }
+    }
+}
+
+impl<const RSIZE: usize, S: Scanner<RSIZE>> Scanner<RSIZE> for GhostFilter<RSIZE, S> {
+    fn scan(&mut self) -> ScanBits<RSIZE> {
+        let mut bits = self.scanner.scan();
+        if !self.enabled {
+            return bits;
+        }
+        let ghosts = bits.ghosts();
+        if ghosts != self.ghosts {
+            if ghosts.is_empty() {
+                warn!("Ghosting cleared");
+            } else {
+                warn!("Ghosting, holding back rows(bit per col): {}", ghosts.rows);
+            }
+            self.ghosts = ghosts;
+        }
+        for ((row, last), ghost) in bits
+            .rows
+            .iter_mut()
+            .zip(self.bits.rows.iter())
+            .zip(ghosts.rows.iter())
+        {
+            *row = *row & !ghost | last & ghost;
+        }
+        self.bits = bits;
+        bits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::FakeScanner;
+
+    fn bits(closed: &[(usize, usize)]) -> ScanBits<3> {
+        let mut bits = ScanBits::new();
+        for (r, c) in closed {
+            bits.set(*r, *c, true);
+        }
+        bits
+    }
+
+    #[test]
+    fn holds_back_a_rectangle() {
+        let mut filter = GhostFilter::new(FakeScanner::<3>::new(), true);
+        let chord = [(0, 0), (0, 1), (1, 0)];
+        filter.scanner.bits = bits(&chord);
+        assert_eq!(filter.scan(), bits(&chord));
+        // the fourth corner reads closed as well without diodes
+        filter.scanner.press(1, 1);
+        assert_eq!(filter.scan(), bits(&chord));
+        // a key outside the rectangle still goes through
+        filter.scanner.press(2, 2);
+        assert_eq!(filter.scan(), bits(&[(0, 0), (0, 1), (1, 0), (2, 2)]));
+        filter.scanner.release(1, 1);
+        filter.scanner.release(2, 2);
+        filter.scanner.release(0, 1);
+        assert_eq!(filter.scan(), bits(&[(0, 0), (1, 0)]));
+    }
+
+    #[test]
+    fn passes_everything_when_disabled() {
+        let mut filter = GhostFilter::new(FakeScanner::<3>::new(), false);
+        let rectangle = bits(&[(0, 0), (0, 1), (1, 0), (1, 1)]);
+        filter.scanner.bits = rectangle;
+        assert_eq!(filter.scan(), rectangle);
+    }
+}
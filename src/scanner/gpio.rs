@@ -0,0 +1,101 @@
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use rp2040_hal::gpio::DynPin;
+
+use super::{DiodeDirection, ScanBits, Scanner, Sense, SETTLE_US};
+
+/// Strobes one side of the matrix and reads the other from core 0 with plain GPIO
+/// Which side strobes follows from the diodes and the sense pulls: the strobe has to drive
+/// current through the diode towards the sense line, e.g. COL2ROW with pull-down rows
+/// strobes the columns high and reads the rows
+pub struct GpioScanner<const RSIZE: usize, const CSIZE: usize> {
+    rows: [DynPin; RSIZE],
+    cols: [DynPin; CSIZE],
+    /// the columns are strobed and the rows read, the other way round otherwise
+    strobe_cols: bool,
+    sense: Sense,
+    /// cpu cycles in SETTLE_US
+    settle_cycles: u32,
+}
+
+impl<const RSIZE: usize, const CSIZE: usize> GpioScanner<RSIZE, CSIZE> {
+    /// sys_hz is the system clock, used to time how long a strobe settles
+    pub fn new(
+        rows: [DynPin; RSIZE],
+        cols: [DynPin; CSIZE],
+        diodes: DiodeDirection,
+        sense: Sense,
+        sys_hz: u32,
+    ) -> Self {
+        let strobe_cols = match diodes {
+            #[cfg(not(feature = "row2col"))]
+            DiodeDirection::Col2Row => sense.active_high(),
+            #[cfg(feature = "row2col")]
+            DiodeDirection::Row2Col => !sense.active_high(),
+        };
+        let mut new = GpioScanner {
+            rows,
+            cols,
+            strobe_cols,
+            sense,
+            settle_cycles: sys_hz / 1_000_000 * SETTLE_US,
+        };
+        let (strobes, senses) = new.lines();
+        for pin in strobes {
+            pin.into_push_pull_output();
+            set_level(pin, !sense.active_high());
+        }
+        for pin in senses {
+            sense.into_input(pin);
+        }
+        new
+    }
+
+    /// (strobe lines, sense lines)
+    fn lines(&mut self) -> (&mut [DynPin], &mut [DynPin]) {
+        if self.strobe_cols {
+            (&mut self.cols, &mut self.rows)
+        } else {
+            (&mut self.rows, &mut self.cols)
+        }
+    }
+}
+
+impl<const RSIZE: usize, const CSIZE: usize> Scanner<RSIZE> for GpioScanner<RSIZE, CSIZE> {
+    /// strobe every line in turn and read every sense line
+    fn scan(&mut self) -> ScanBits<RSIZE> {
+        let mut bits = ScanBits::new();
+        let sense = self.sense;
+        let settle_cycles = self.settle_cycles;
+        let strobe_cols = self.strobe_cols;
+        let (strobes, senses) = self.lines();
+        for (s, strobe) in strobes.iter_mut().enumerate() {
+            // Drain stray potential from floating sense lines
+            #[cfg(not(any(feature = "sense-pull-up", feature = "sense-pull-down")))]
+            for pin in senses.iter_mut() {
+                pin.into_push_pull_output();
+                pin.set_low().unwrap();
+                pin.into_floating_input();
+            }
+            set_level(strobe, sense.active_high());
+            cortex_m::asm::delay(settle_cycles);
+            for (i, pin) in senses.iter_mut().enumerate() {
+                let closed = pin.is_high().unwrap() == sense.active_high();
+                if strobe_cols {
+                    bits.set(i, s, closed);
+                } else {
+                    bits.set(s, i, closed);
+                }
+            }
+            set_level(strobe, !sense.active_high());
+        }
+        bits
+    }
+}
+
+fn set_level(pin: &mut DynPin, high: bool) {
+    if high {
+        pin.set_high().unwrap()
+    } else {
+        pin.set_low().unwrap()
+    }
+}
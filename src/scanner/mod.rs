@@ -0,0 +1,144 @@
+use defmt::Format;
+#[cfg(not(feature = "pio-scan"))]
+use rp2040_hal::gpio::DynPin;
+
+pub mod chatter;
+#[cfg(feature = "direct-pins")]
+mod direct;
+#[cfg(test)]
+mod fake;
+mod ghost;
+// PIO1 does all the strobing with pio-scan
+#[cfg(not(any(feature = "pio-scan", feature = "direct-pins")))]
+mod gpio;
+#[cfg(feature = "pio-scan")]
+mod pio;
+
+pub use chatter::ChatterFilter;
+#[cfg(feature = "direct-pins")]
+pub use direct::DirectScanner;
+// runs the key logic in host tests
+#[cfg(test)]
+pub use fake::FakeScanner;
+pub use ghost::GhostFilter;
+#[cfg(not(any(feature = "pio-scan", feature = "direct-pins")))]
+pub use gpio::GpioScanner;
+#[cfg(feature = "pio-scan")]
+pub use pio::PioScanner;
+
+/// Time(us) a column is driven before the rows are read
+#[cfg(not(feature = "direct-pins"))]
+pub const SETTLE_US: u32 = 10;
+
+/// Which way the matrix diodes point, current flows from the named side to the other
+/// e.g. COL2ROW has the anode on the column and the cathode on the row
+/// Only the one picked by the row2col feature is built, see board::DIODES
+#[cfg(not(any(feature = "pio-scan", feature = "direct-pins")))]
+#[derive(Copy, Clone, PartialEq, Debug, Format)]
+pub enum DiodeDirection {
+    #[cfg(not(feature = "row2col"))]
+    Col2Row,
+    #[cfg(feature = "row2col")]
+    Row2Col,
+}
+
+/// How the sense lines rest while their switch is open
+/// Only the one picked by the sense-* features is built, see board::SENSE
+#[cfg(not(feature = "pio-scan"))]
+#[derive(Copy, Clone, PartialEq, Debug, Format)]
+pub enum Sense {
+    /// internal pull-downs, a closed switch reads high
+    #[cfg(feature = "sense-pull-down")]
+    PullDown,
+    /// internal pull-ups, a closed switch reads low
+    #[cfg(feature = "sense-pull-up")]
+    PullUp,
+    /// no pulls, the lines are drained low before every strobe and a closed switch reads high
+    #[cfg(not(any(feature = "sense-pull-up", feature = "sense-pull-down")))]
+    Floating,
+}
+
+#[cfg(not(feature = "pio-scan"))]
+impl Sense {
+    /// a closed switch reads high
+    fn active_high(self) -> bool {
+        match self {
+            #[cfg(feature = "sense-pull-down")]
+            Sense::PullDown => true,
+            #[cfg(feature = "sense-pull-up")]
+            Sense::PullUp => false,
+            #[cfg(not(any(feature = "sense-pull-up", feature = "sense-pull-down")))]
+            Sense::Floating => true,
+        }
+    }
+    fn into_input(self, pin: &mut DynPin) {
+        match self {
+            #[cfg(feature = "sense-pull-down")]
+            Sense::PullDown => pin.into_pull_down_input(),
+            #[cfg(feature = "sense-pull-up")]
+            Sense::PullUp => pin.into_pull_up_input(),
+            #[cfg(not(any(feature = "sense-pull-up", feature = "sense-pull-down")))]
+            Sense::Floating => pin.into_floating_input(),
+        }
+    }
+}
+
+/// Reads the state of every switch in the matrix
+/// The key logic in Matrix only ever sees these snapshots so it doesn't care how they're taken
+pub trait Scanner<const RSIZE: usize> {
+    /// the switches closed right now(or as of the last finished hardware scan)
+    fn scan(&mut self) -> ScanBits<RSIZE>;
+}
+
+/// One bit per switch, bit c of rows[r] is set while the switch at row r, col c is closed
+#[derive(Copy, Clone, PartialEq, Debug, Format)]
+pub struct ScanBits<const RSIZE: usize> {
+    pub rows: [u32; RSIZE],
+}
+
+impl<const RSIZE: usize> ScanBits<RSIZE> {
+    pub const fn new() -> Self {
+        ScanBits { rows: [0; RSIZE] }
+    }
+    pub fn get(&self, row: usize, col: usize) -> bool {
+        self.rows[row] & (1 << col) != 0
+    }
+    pub fn set(&mut self, row: usize, col: usize, closed: bool) {
+        if closed {
+            self.rows[row] |= 1 << col;
+        } else {
+            self.rows[row] &= !(1 << col);
+        }
+    }
+    /// switches that could be ghosts, every corner of a rectangle of closed switches
+    /// Without diodes three closed corners close the fourth too, so no corner can be trusted
+    pub fn ghosts(&self) -> Self {
+        let mut ghosts = Self::new();
+        for a in 0..RSIZE {
+            for b in a + 1..RSIZE {
+                let shared = self.rows[a] & self.rows[b];
+                if shared.count_ones() >= 2 {
+                    ghosts.rows[a] |= shared;
+                    ghosts.rows[b] |= shared;
+                }
+            }
+        }
+        ghosts
+    }
+    pub fn is_empty(&self) -> bool {
+        self.rows.iter().all(|r| *r == 0)
+    }
+    /// (row, col) of every switch that differs between the two scans
+    pub fn changed<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.rows
+            .iter()
+            .zip(other.rows.iter())
+            .enumerate()
+            .flat_map(|(r, (a, b))| {
+                let diff = a ^ b;
+                (0..32)
+                    .filter(move |c| diff & (1 << c) != 0)
+                    .map(move |c| (r, c))
+            })
+    }
+}
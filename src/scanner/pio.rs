@@ -0,0 +1,147 @@
+use rp2040_hal::dma::{single_buffer, Channel, CH0, CH1};
+use rp2040_hal::gpio::{dynpin::DYN_FUNCTION_PIO1, DynPin};
+use rp2040_hal::pac::PIO1;
+use rp2040_hal::pio::{
+    PIOBuilder, PinDir, Running, Rx, ShiftDirection, StateMachine, Tx, UninitStateMachine, PIO, SM0,
+};
+
+use super::{ScanBits, Scanner, SETTLE_US};
+use crate::{MATRIX_COLS, MATRIX_ROWS};
+
+type ColsTransfer =
+    single_buffer::Transfer<Channel<CH0>, &'static [u32; MATRIX_COLS], Tx<(PIO1, SM0)>>;
+type RowsTransfer =
+    single_buffer::Transfer<Channel<CH1>, Rx<(PIO1, SM0)>, &'static mut [u32; MATRIX_COLS]>;
+
+/// Pin mask to drive for each column, the PIO outputs it as is
+static mut STROBES: [u32; MATRIX_COLS] = [0; MATRIX_COLS];
+/// Row pins read while each column was driven, filled by DMA
+static mut SAMPLES: [u32; MATRIX_COLS] = [0; MATRIX_COLS];
+
+/// Strobes the columns and samples the rows in a PIO1 state machine
+/// DMA feeds it the column masks and copies the samples back to RAM so a scan costs core 0
+/// nothing but waiting for the previous sweep and turning its samples into ScanBits
+/// The rows have to be consecutive pins, the columns can be anywhere
+pub struct PioScanner {
+    /// the last sweep, None only while scan swaps it for the next one
+    transfer: Option<(ColsTransfer, RowsTransfer)>,
+    /// bit of each row in a sample
+    row_bits: [u32; MATRIX_ROWS],
+    _sm: StateMachine<(PIO1, SM0), Running>,
+    _pio: PIO<PIO1>,
+}
+
+impl PioScanner {
+    /// sys_hz is the system clock, the state machine runs at 1MHz so a delay cycle is 1us
+    pub fn new(
+        mut pio: PIO<PIO1>,
+        sm: UninitStateMachine<(PIO1, SM0)>,
+        cols_ch: Channel<CH0>,
+        rows_ch: Channel<CH1>,
+        mut rows: [DynPin; MATRIX_ROWS],
+        mut cols: [DynPin; MATRIX_COLS],
+        sys_hz: u32,
+    ) -> Self {
+        let row_base = rows.iter().map(|p| p.id().num).min().unwrap_or(0);
+        let row_mask = (1u32 << MATRIX_ROWS) - 1;
+        let mut row_bits = [0; MATRIX_ROWS];
+        for (bit, pin) in row_bits.iter_mut().zip(rows.iter_mut()) {
+            let offset = pin.id().num - row_base;
+            assert!(
+                (offset as usize) < MATRIX_ROWS,
+                "PIO rows must be consecutive pins"
+            );
+            *bit = 1 << offset;
+            pin.try_into_mode(DYN_FUNCTION_PIO1).unwrap();
+        }
+        let strobes = unsafe { &mut STROBES };
+        for (strobe, pin) in strobes.iter_mut().zip(cols.iter_mut()) {
+            *strobe = 1 << pin.id().num;
+            pin.try_into_mode(DYN_FUNCTION_PIO1).unwrap();
+        }
+
+        let mut a = pio::Assembler::<32>::new();
+        let mut wrap_target = a.label();
+        let mut wrap_source = a.label();
+        let mut settle = a.label();
+        a.bind(&mut wrap_target);
+        // wait for the next column mask
+        a.pull(false, true);
+        // Drain stray potential from sense lines
+        a.set(pio::SetDestination::PINS, 0);
+        a.set(pio::SetDestination::PINDIRS, row_mask as u8);
+        a.set(pio::SetDestination::PINDIRS, 0);
+        a.out(pio::OutDestination::PINS, 32);
+        a.set(pio::SetDestination::X, (SETTLE_US - 1) as u8);
+        a.bind(&mut settle);
+        a.jmp(pio::JmpCondition::XDecNonZero, &mut settle);
+        a.r#in(pio::InSource::PINS, MATRIX_ROWS as u8);
+        a.push(false, true);
+        a.mov(
+            pio::MovDestination::PINS,
+            pio::MovOperation::None,
+            pio::MovSource::NULL,
+        );
+        a.bind(&mut wrap_source);
+        let program = a.assemble_with_wrap(wrap_source, wrap_target);
+        let installed = pio.install(&program).unwrap();
+
+        let (mut sm, rx, tx) = PIOBuilder::from_program(installed)
+            .out_pins(0, 32)
+            .set_pins(row_base, MATRIX_ROWS as u8)
+            .in_pin_base(row_base)
+            .in_shift_direction(ShiftDirection::Left)
+            .out_shift_direction(ShiftDirection::Right)
+            .clock_divisor_fixed_point((sys_hz / 1_000_000) as u16, 0)
+            .build(sm);
+        sm.set_pindirs(
+            strobes
+                .iter()
+                .map(|s| (s.trailing_zeros() as u8, PinDir::Output))
+                .chain((0..MATRIX_ROWS as u8).map(|r| (row_base + r, PinDir::Input))),
+        );
+        let sm = sm.start();
+
+        PioScanner {
+            transfer: Some(Self::sweep(cols_ch, rows_ch, tx, rx, strobes, unsafe {
+                &mut SAMPLES
+            })),
+            row_bits,
+            _sm: sm,
+            _pio: pio,
+        }
+    }
+
+    /// start DMA feeding every column mask in and every sample out
+    fn sweep(
+        cols_ch: Channel<CH0>,
+        rows_ch: Channel<CH1>,
+        tx: Tx<(PIO1, SM0)>,
+        rx: Rx<(PIO1, SM0)>,
+        strobes: &'static [u32; MATRIX_COLS],
+        samples: &'static mut [u32; MATRIX_COLS],
+    ) -> (ColsTransfer, RowsTransfer) {
+        // start reading first so the samples never back up in the FIFO
+        let rows = single_buffer::Config::new(rows_ch, rx, samples).start();
+        let cols = single_buffer::Config::new(cols_ch, strobes, tx).start();
+        (cols, rows)
+    }
+}
+
+impl Scanner<MATRIX_ROWS> for PioScanner {
+    /// waits for the running sweep, starts the next one and returns the finished one
+    /// so the snapshot is at most one poll old
+    fn scan(&mut self) -> ScanBits<MATRIX_ROWS> {
+        let (cols, rows) = self.transfer.take().unwrap();
+        let (cols_ch, strobes, tx) = cols.wait();
+        let (rows_ch, rx, samples) = rows.wait();
+        let mut bits = ScanBits::new();
+        for (c, sample) in samples.iter().enumerate() {
+            for (r, bit) in self.row_bits.iter().enumerate() {
+                bits.set(r, c, sample & bit != 0);
+            }
+        }
+        self.transfer = Some(Self::sweep(cols_ch, rows_ch, tx, rx, strobes, samples));
+        bits
+    }
+}
@@ -0,0 +1,179 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+#[cfg(feature = "hand-flash")]
+use defmt::warn;
+use defmt::{info, Format};
+#[cfg(feature = "hand-strap")]
+use embedded_hal::digital::v2::InputPin;
+#[cfg(feature = "hand-strap")]
+use rp2040_hal::gpio::DynPin;
+
+use crate::scanner::ScanBits;
+use crate::{board, storage, MATRIX_ROWS};
+
+/// Marks a saved hand in the identity sector, "HAND"
+const MAGIC: u32 = 0x4841_4e44;
+/// Where the hand is kept in the identity sector
+#[cfg(feature = "hand-flash")]
+const HAND_OFFSET: usize = 0;
+
+/// The hand in use, 0 until it is known
+static HAND: AtomicU8 = AtomicU8::new(0);
+/// The hand waiting to be saved by poll, 0 when there is none, encoded like HAND
+static SAVE: AtomicU8 = AtomicU8::new(0);
+
+/// Which half of a split this is
+#[derive(Copy, Clone, PartialEq, Debug, Format)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+/// Where a half learns its hand, see board::HAND_FROM
+/// Only the one picked by the hand-* features is built
+#[derive(Copy, Clone, PartialEq, Debug, Format)]
+pub enum HandSource {
+    /// the pin from board::hand_strap! is pulled up, the right half ties it to ground
+    #[cfg(feature = "hand-strap")]
+    Strap,
+    /// saved in flash by "ergoone-cli hand left|right", halves without one go by USB
+    #[cfg(feature = "hand-flash")]
+    Flash,
+    /// the half plugged into USB is the left one
+    #[cfg(not(any(feature = "hand-strap", feature = "hand-flash")))]
+    Usb,
+}
+
+impl Hand {
+    pub fn other(self) -> Self {
+        match self {
+            Hand::Left => Hand::Right,
+            Hand::Right => Hand::Left,
+        }
+    }
+
+    /// where column col of this half sits in the keymap
+    /// Both halves count their columns from the outside in, the right half is the left
+    /// one mirrored
+    pub fn global_col(self, col: usize) -> usize {
+        match self {
+            Hand::Left => col,
+            Hand::Right => board::MATRIX_COLS - 1 - col,
+        }
+    }
+
+    /// the switches of this half as wired, placed in the keymap
+    pub fn to_global(self, local: [u32; MATRIX_ROWS]) -> ScanBits<MATRIX_ROWS> {
+        let mut bits = ScanBits::new();
+        for (row, cols) in local.iter().enumerate() {
+            for col in (0..board::HALF_COLS).filter(|c| cols & (1 << c) != 0) {
+                bits.set(row, self.global_col(col), true);
+            }
+        }
+        bits
+    }
+}
+
+/// read the strap, the pin is left pulled up
+#[cfg(feature = "hand-strap")]
+pub fn from_strap(mut pin: DynPin) -> Hand {
+    pin.into_pull_up_input();
+    // give the pull-up time to charge the pin before reading it
+    cortex_m::asm::delay(10_000);
+    if pin.is_low().unwrap() {
+        Hand::Right
+    } else {
+        Hand::Left
+    }
+}
+
+/// the hand saved in flash, if any
+#[cfg(feature = "hand-flash")]
+pub fn load() -> Option<Hand> {
+    let mut buf = [0u8; 5];
+    storage::read_identity(HAND_OFFSET, &mut buf);
+    if u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) != MAGIC {
+        warn!("No hand saved in flash, going by USB");
+        return None;
+    }
+    match buf[4] {
+        0 => Some(Hand::Left),
+        1 => Some(Hand::Right),
+        _ => None,
+    }
+}
+
+/// save the hand to flash at the next poll, used from the next boot on
+/// HID-IO commands run in a critical section, which the flash write mustn't
+pub fn save(hand: Hand) {
+    SAVE.store(hand as u8 + 1, Ordering::Relaxed);
+}
+
+/// write the hand save asked for, called from the main loop outside any critical section
+pub fn poll() {
+    let hand = match SAVE.load(Ordering::Relaxed) {
+        1 => Hand::Left,
+        2 => Hand::Right,
+        _ => return,
+    };
+    SAVE.store(0, Ordering::Relaxed);
+    let mut buf = [0u8; 5];
+    buf[..4].copy_from_slice(&MAGIC.to_le_bytes());
+    buf[4] = hand as u8;
+    info!("Saving hand {}", hand);
+    storage::write_identity(&buf);
+}
+
+/// the hand in use, None until a half going by USB knows whether it has USB
+pub fn current() -> Option<Hand> {
+    match HAND.load(Ordering::Relaxed) {
+        1 => Some(Hand::Left),
+        2 => Some(Hand::Right),
+        _ => None,
+    }
+}
+
+pub(super) fn set(hand: Hand) {
+    if current() != Some(hand) {
+        info!("This is the {} half", hand);
+        HAND.store(hand as u8 + 1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Hand;
+    use crate::board::{HALF_COLS, MATRIX_COLS};
+    use crate::MATRIX_ROWS;
+
+    #[test]
+    fn right_half_is_mirrored() {
+        assert_eq!(Hand::Left.global_col(0), 0);
+        assert_eq!(Hand::Left.global_col(HALF_COLS - 1), HALF_COLS - 1);
+        // both count from the outside in, so the right's column 0 is the last one
+        assert_eq!(Hand::Right.global_col(0), MATRIX_COLS - 1);
+        assert_eq!(Hand::Right.global_col(HALF_COLS - 1), HALF_COLS);
+    }
+
+    #[test]
+    fn local_switches_land_in_their_half() {
+        let mut local = [0; MATRIX_ROWS];
+        local[0] = 0b101;
+        local[MATRIX_ROWS - 1] = 1 << (HALF_COLS - 1);
+        let left = Hand::Left.to_global(local);
+        let right = Hand::Right.to_global(local);
+        assert_eq!(left.rows[0], 0b101);
+        assert_eq!(left.rows[MATRIX_ROWS - 1], 1 << (HALF_COLS - 1));
+        assert!(right.get(0, MATRIX_COLS - 1) && right.get(0, MATRIX_COLS - 3));
+        assert!(right.get(MATRIX_ROWS - 1, HALF_COLS));
+        assert_eq!(right.rows.iter().map(|r| r.count_ones()).sum::<u32>(), 3);
+    }
+
+    #[test]
+    fn columns_past_the_half_are_dropped() {
+        let mut local = [0; MATRIX_ROWS];
+        local[0] = 1 << HALF_COLS;
+        assert!(Hand::Left.to_global(local).is_empty());
+        assert!(Hand::Right.to_global(local).is_empty());
+    }
+}
@@ -0,0 +1,66 @@
+#[cfg(test)]
+use heapless::Deque;
+use rp2040_hal::uart::{Enabled, UartDevice, UartPeripheral, ValidUartPinout};
+
+/// Bytes to and from the other half
+pub trait Link {
+    /// send bytes to the other half, whatever doesn't fit right now is dropped
+    /// and the checksum makes the other side throw the frame away
+    fn write(&mut self, bytes: &[u8]);
+    /// the next byte from the other half
+    fn read(&mut self) -> Option<u8>;
+}
+
+/// Hands every byte written straight back, to run both ends of the protocol without a cable
+#[cfg(test)]
+pub struct Loopback<const N: usize> {
+    bytes: Deque<u8, N>,
+}
+
+#[cfg(test)]
+impl<const N: usize> Loopback<N> {
+    pub const fn new() -> Self {
+        Loopback {
+            bytes: Deque::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl<const N: usize> Link for Loopback<N> {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            if self.bytes.push_back(byte).is_err() {
+                return;
+            }
+        }
+    }
+    fn read(&mut self) -> Option<u8> {
+        self.bytes.pop_front()
+    }
+}
+
+/// The link over a UART, TX of each half goes to RX of the other
+pub struct UartLink<D: UartDevice, P: ValidUartPinout<D>> {
+    uart: UartPeripheral<Enabled, D, P>,
+}
+
+impl<D: UartDevice, P: ValidUartPinout<D>> UartLink<D, P> {
+    pub fn new(uart: UartPeripheral<Enabled, D, P>) -> Self {
+        UartLink { uart }
+    }
+}
+
+impl<D: UartDevice, P: ValidUartPinout<D>> Link for UartLink<D, P> {
+    fn write(&mut self, bytes: &[u8]) {
+        // a frame fits the 32 byte FIFO, never wait on it from the scan loop
+        self.uart.write_raw(bytes).ok();
+    }
+    fn read(&mut self) -> Option<u8> {
+        let mut byte = [0u8; 1];
+        match self.uart.read_raw(&mut byte) {
+            Ok(1) => Some(byte[0]),
+            _ => None,
+        }
+    }
+}
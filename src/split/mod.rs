@@ -0,0 +1,257 @@
+pub mod hand;
+pub mod link;
+pub mod protocol;
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use defmt::{info, warn, Format};
+use usb_device::prelude::UsbDeviceState;
+
+use crate::rgb::{self, Effect, EffectSettings, Hsv, IndicatorState};
+use crate::scanner::{ScanBits, Scanner};
+use crate::{host_leds, MATRIX_ROWS};
+use hand::Hand;
+use link::Link;
+use protocol::{Decoder, Lighting, Message, MAX_FRAME};
+
+/// Time(ms) between resends of an unchanged message, doubles as the link heartbeat
+const RESEND_MS: u32 = 100;
+/// The other half counts as gone after this long(ms) without a good frame
+const LINK_TIMEOUT_MS: u32 = 500;
+
+/// Set once the host configured this half, it then stays the primary until reset
+static USB_CONFIGURED: AtomicBool = AtomicBool::new(false);
+
+/// What a half does on the link, both halves run the same firmware and find out at runtime
+#[derive(Copy, Clone, PartialEq, Debug, Format)]
+pub enum Role {
+    /// plugged into USB, runs the keymap with the switches of both halves
+    Primary,
+    /// only streams its switches to the primary and shows the LEDs it is told to
+    Secondary,
+}
+
+/// called from the USB interrupt with the current device state
+pub fn set_usb_state(state: UsbDeviceState) {
+    if state == UsbDeviceState::Configured {
+        USB_CONFIGURED.store(true, Ordering::Relaxed);
+    }
+}
+
+/// One end of the link between the halves
+pub struct SplitLink<L: Link> {
+    link: L,
+    decoder: Decoder,
+    /// the last message sent and when
+    sent: Option<(Message, u32)>,
+    /// time(ms) of the last good frame
+    received_at: Option<u32>,
+    /// decoder errors already logged
+    errors: u32,
+}
+
+impl<L: Link> SplitLink<L> {
+    pub const fn new(link: L) -> Self {
+        SplitLink {
+            link,
+            decoder: Decoder::new(),
+            sent: None,
+            received_at: None,
+            errors: 0,
+        }
+    }
+
+    /// send msg if it changed or RESEND_MS passed since it was last sent
+    pub fn send(&mut self, msg: Message, now: u32) {
+        if let Some((sent, at)) = self.sent {
+            if sent == msg && now.wrapping_sub(at) < RESEND_MS {
+                return;
+            }
+        }
+        let mut buf = [0; MAX_FRAME];
+        self.link.write(msg.encode(&mut buf));
+        self.sent = Some((msg, now));
+    }
+
+    /// the next message from the other half
+    pub fn recv(&mut self, now: u32) -> Option<Message> {
+        while let Some(byte) = self.link.read() {
+            let msg = self.decoder.push(byte);
+            if self.decoder.errors != self.errors {
+                self.errors = self.decoder.errors;
+                warn!("Split link dropped a bad frame, {} so far", self.errors);
+            }
+            if msg.is_some() {
+                if !self.is_up(now) {
+                    info!("Split link up");
+                }
+                self.received_at = Some(now);
+                return msg;
+            }
+        }
+        None
+    }
+
+    /// a good frame came in within LINK_TIMEOUT_MS
+    pub fn is_up(&self, now: u32) -> bool {
+        self.received_at
+            .is_some_and(|at| now.wrapping_sub(at) < LINK_TIMEOUT_MS)
+    }
+}
+
+/// Wraps the scanner of this half and joins it with the other half over the link
+/// A half becomes the primary once the host configures it and the secondary once it hears
+/// from a primary, until then it acts as both
+/// On the primary the scan holds both halves in keymap coordinates and the LED state goes
+/// the other way. The other half's switches come as wired and are placed as the other hand
+/// On the secondary the scan is always empty so no key runs, its switches go to the primary
+pub struct SplitScanner<S: Scanner<MATRIX_ROWS>, L: Link> {
+    local: S,
+    link: SplitLink<L>,
+    /// None until it is known
+    role: Option<Role>,
+    /// from the strap or flash, None to go by USB
+    hand: Option<Hand>,
+    /// current time(ms)
+    now: fn() -> u32,
+    /// the other half's switches as wired, unused on the secondary
+    remote: [u32; MATRIX_ROWS],
+    /// the link was up in the last scan
+    up: bool,
+}
+
+impl<S: Scanner<MATRIX_ROWS>, L: Link> SplitScanner<S, L> {
+    pub fn new(local: S, link: SplitLink<L>, hand: Option<Hand>, now: fn() -> u32) -> Self {
+        let mut new = SplitScanner {
+            local,
+            link,
+            role: None,
+            hand,
+            now,
+            remote: [0; MATRIX_ROWS],
+            up: false,
+        };
+        new.set_hand();
+        new
+    }
+
+    fn set_role(&mut self, role: Role) {
+        info!("This half is the {}", role);
+        self.role = Some(role);
+        self.set_hand();
+    }
+
+    /// a half going by USB counts as the left one until it turns out to be the secondary
+    fn set_hand(&mut self) {
+        let hand = match (self.hand, self.role) {
+            (Some(hand), _) => hand,
+            (None, Some(Role::Secondary)) => Hand::Right,
+            (None, _) => Hand::Left,
+        };
+        hand::set(hand);
+    }
+}
+
+impl<S: Scanner<MATRIX_ROWS>, L: Link> Scanner<MATRIX_ROWS> for SplitScanner<S, L> {
+    fn scan(&mut self) -> ScanBits<MATRIX_ROWS> {
+        let now = (self.now)();
+        if self.role.is_none() && USB_CONFIGURED.load(Ordering::Relaxed) {
+            self.set_role(Role::Primary);
+        }
+        let local = self.local.scan();
+        while let Some(msg) = self.link.recv(now) {
+            match (self.role, msg) {
+                (Some(Role::Primary) | None, Message::Switches(rows)) => self.remote = rows,
+                (None, Message::Lighting(lighting)) => {
+                    self.set_role(Role::Secondary);
+                    apply_lighting(lighting);
+                }
+                (Some(Role::Secondary), Message::Lighting(lighting)) => apply_lighting(lighting),
+                (Some(role), _) => warn!("{} got {}, are both halves on USB?", role, msg),
+            }
+        }
+        let up = self.link.is_up(now);
+        if self.up && !up {
+            // don't leave the other half's keys held down
+            warn!("Split link lost");
+            self.remote = [0; MATRIX_ROWS];
+        }
+        self.up = up;
+        match self.role {
+            Some(Role::Primary) => self.link.send(Message::Lighting(lighting()), now),
+            _ => self.link.send(Message::Switches(local.rows), now),
+        }
+        if self.role == Some(Role::Secondary) {
+            return ScanBits::new();
+        }
+        let hand = hand::current().unwrap_or(Hand::Left);
+        let mut bits = hand.to_global(local.rows);
+        let remote = hand.other().to_global(self.remote);
+        for (row, remote) in bits.rows.iter_mut().zip(remote.rows.iter()) {
+            *row |= remote;
+        }
+        bits
+    }
+}
+
+/// the LED state the secondary should mirror
+fn lighting() -> Lighting {
+    let config = rgb::config();
+    let settings = rgb::effect_settings();
+    Lighting {
+        effect: settings.effect as u8,
+        hue: settings.color.h,
+        sat: settings.color.s,
+        val: settings.color.v,
+        speed: settings.speed,
+        brightness: config.brightness,
+        layer: config.indicators.layer,
+        mods: config.indicators.mods,
+        host_leds: host_leds::get().bits(),
+    }
+}
+
+fn apply_lighting(lighting: Lighting) {
+    rgb::set_shared(
+        EffectSettings {
+            effect: Effect::from(lighting.effect),
+            color: Hsv::new(lighting.hue, lighting.sat, lighting.val),
+            speed: lighting.speed,
+        },
+        lighting.brightness,
+        IndicatorState {
+            layer: lighting.layer,
+            mods: lighting.mods,
+        },
+    );
+    host_leds::set(lighting.host_leds);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::MATRIX_COLS;
+    use crate::scanner::FakeScanner;
+    use link::Loopback;
+
+    fn now() -> u32 {
+        0
+    }
+
+    #[test]
+    fn scan_merges_both_halves() {
+        let mut local = FakeScanner::<MATRIX_ROWS>::new();
+        local.bits.set(0, 0, true);
+        // what the other half sent: its column 0 in row 1
+        let mut rows = [0; MATRIX_ROWS];
+        rows[1] = 0b1;
+        let mut link = Loopback::<256>::new();
+        let mut buf = [0; MAX_FRAME];
+        link.write(Message::Switches(rows).encode(&mut buf));
+        let mut scanner = SplitScanner::new(local, SplitLink::new(link), Some(Hand::Left), now);
+        let bits = scanner.scan();
+        assert!(bits.get(0, 0));
+        assert!(bits.get(1, MATRIX_COLS - 1));
+        assert_eq!(bits.rows.iter().map(|r| r.count_ones()).sum::<u32>(), 2);
+    }
+}
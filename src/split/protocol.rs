@@ -0,0 +1,309 @@
+use defmt::Format;
+use heapless::Deque;
+
+use crate::MATRIX_ROWS;
+
+/// Marks the start of every frame, a receiver that lost track skips ahead to the next one
+const START: u8 = 0x7e;
+const KIND_SWITCHES: u8 = 1;
+const KIND_LIGHTING: u8 = 2;
+const MAX_PAYLOAD: usize = MATRIX_ROWS * 4;
+/// start, kind, payload length, payload and checksum
+pub const MAX_FRAME: usize = MAX_PAYLOAD + 4;
+
+/// What the LEDs of the secondary half show, the primary owns the settings
+#[derive(Copy, Clone, PartialEq, Debug, Format)]
+pub struct Lighting {
+    pub effect: u8,
+    pub hue: u8,
+    pub sat: u8,
+    pub val: u8,
+    pub speed: u8,
+    pub brightness: u8,
+    pub layer: u8,
+    pub mods: u8,
+    /// lock LEDs from the host, see host_leds
+    pub host_leds: u8,
+}
+
+/// Everything sent between the halves
+#[derive(Copy, Clone, PartialEq, Debug, Format)]
+pub enum Message {
+    /// every switch of the sending half as wired, bit c of rows[r] is its switch at row r,
+    /// col c counted from the outside in
+    Switches([u32; MATRIX_ROWS]),
+    Lighting(Lighting),
+}
+
+impl Message {
+    /// write the framed message into buf and return the frame
+    pub fn encode<'a>(&self, buf: &'a mut [u8; MAX_FRAME]) -> &'a [u8] {
+        let mut payload = [0u8; MAX_PAYLOAD];
+        let (kind, len) = match self {
+            Message::Switches(rows) => {
+                for (bytes, row) in payload.chunks_mut(4).zip(rows.iter()) {
+                    bytes.copy_from_slice(&row.to_le_bytes());
+                }
+                (KIND_SWITCHES, MATRIX_ROWS * 4)
+            }
+            Message::Lighting(l) => {
+                let fields = [
+                    l.effect,
+                    l.hue,
+                    l.sat,
+                    l.val,
+                    l.speed,
+                    l.brightness,
+                    l.layer,
+                    l.mods,
+                    l.host_leds,
+                ];
+                payload[..fields.len()].copy_from_slice(&fields);
+                (KIND_LIGHTING, fields.len())
+            }
+        };
+        buf[0] = START;
+        buf[1] = kind;
+        buf[2] = len as u8;
+        buf[3..3 + len].copy_from_slice(&payload[..len]);
+        buf[3 + len] = crc8(&buf[1..3 + len]);
+        &buf[..4 + len]
+    }
+
+    fn decode(kind: u8, payload: &[u8]) -> Option<Self> {
+        match (kind, payload.len()) {
+            (KIND_SWITCHES, len) if len == MATRIX_ROWS * 4 => {
+                let mut rows = [0; MATRIX_ROWS];
+                for (row, bytes) in rows.iter_mut().zip(payload.chunks(4)) {
+                    *row = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                }
+                Some(Message::Switches(rows))
+            }
+            (KIND_LIGHTING, 9) => Some(Message::Lighting(Lighting {
+                effect: payload[0],
+                hue: payload[1],
+                sat: payload[2],
+                val: payload[3],
+                speed: payload[4],
+                brightness: payload[5],
+                layer: payload[6],
+                mods: payload[7],
+                host_leds: payload[8],
+            })),
+            _ => None,
+        }
+    }
+}
+
+/// Turns the bytes from the other half back into messages
+pub struct Decoder {
+    buf: [u8; MAX_FRAME],
+    len: usize,
+    /// bytes received but not decoded yet, a dropped frame puts what followed its start
+    /// back here since the next frame may begin inside it
+    replay: Deque<u8, REPLAY_SIZE>,
+    /// frames dropped for a bad checksum, length or kind
+    pub errors: u32,
+}
+
+/// Room for a dropped frame and the bytes left over after the message found in it
+const REPLAY_SIZE: usize = 2 * MAX_FRAME;
+
+impl Decoder {
+    pub const fn new() -> Self {
+        Decoder {
+            buf: [0; MAX_FRAME],
+            len: 0,
+            replay: Deque::new(),
+            errors: 0,
+        }
+    }
+
+    /// feed the next byte, returns a message once a whole good frame came in
+    /// one message comes out per call, bytes after it wait for the next one
+    pub fn push(&mut self, byte: u8) -> Option<Message> {
+        if self.replay.push_back(byte).is_err() {
+            // can't happen with REPLAY_SIZE, start over rather than lose track
+            self.errors = self.errors.wrapping_add(1);
+            self.replay.clear();
+            self.len = 0;
+            return None;
+        }
+        while let Some(byte) = self.replay.pop_front() {
+            if let Some(msg) = self.decode(byte) {
+                return Some(msg);
+            }
+        }
+        None
+    }
+
+    fn decode(&mut self, byte: u8) -> Option<Message> {
+        if self.len == 0 {
+            if byte == START {
+                self.buf[0] = byte;
+                self.len = 1;
+            }
+            return None;
+        }
+        self.buf[self.len] = byte;
+        self.len += 1;
+        if self.len < 3 {
+            return None;
+        }
+        let payload_len = self.buf[2] as usize;
+        if payload_len > MAX_PAYLOAD {
+            self.resync();
+            return None;
+        }
+        if self.len < payload_len + 4 {
+            return None;
+        }
+        let frame = &self.buf[..self.len];
+        let msg = if crc8(&frame[1..frame.len() - 1]) == frame[frame.len() - 1] {
+            Message::decode(frame[1], &frame[3..frame.len() - 1])
+        } else {
+            None
+        };
+        if msg.is_none() {
+            self.resync();
+            return None;
+        }
+        self.len = 0;
+        msg
+    }
+
+    /// drop a bad frame and go through what came after its start again
+    fn resync(&mut self) {
+        self.errors = self.errors.wrapping_add(1);
+        for &byte in self.buf[1..self.len].iter().rev() {
+            // there is always room, see REPLAY_SIZE
+            self.replay.push_front(byte).ok();
+        }
+        self.len = 0;
+    }
+}
+
+/// CRC-8 with the 0x07 polynomial
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                crc << 1 ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::split::link::{Link, Loopback};
+
+    const LIGHTING: Message = Message::Lighting(Lighting {
+        effect: 2,
+        hue: 170,
+        sat: 255,
+        val: 128,
+        speed: 64,
+        brightness: 200,
+        layer: 1,
+        mods: 0x22,
+        host_leds: 0b10,
+    });
+
+    fn switches() -> Message {
+        let mut rows = [0; MATRIX_ROWS];
+        rows[0] = 0b1;
+        rows[MATRIX_ROWS - 1] = 0x8000_0000;
+        Message::Switches(rows)
+    }
+
+    /// the frame msg is sent as
+    fn frame(msg: &Message) -> heapless::Vec<u8, MAX_FRAME> {
+        let mut buf = [0; MAX_FRAME];
+        heapless::Vec::from_slice(msg.encode(&mut buf)).unwrap()
+    }
+
+    /// everything the decoder makes of the bytes on the link
+    fn received(link: &mut Loopback<256>, decoder: &mut Decoder) -> heapless::Vec<Message, 8> {
+        let mut msgs = heapless::Vec::new();
+        while let Some(byte) = link.read() {
+            if let Some(msg) = decoder.push(byte) {
+                msgs.push(msg).unwrap();
+            }
+        }
+        msgs
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut link = Loopback::<256>::new();
+        let mut decoder = Decoder::new();
+        link.write(&frame(&switches()));
+        link.write(&frame(&LIGHTING));
+        assert_eq!(received(&mut link, &mut decoder), [switches(), LIGHTING]);
+        assert_eq!(decoder.errors, 0);
+    }
+
+    #[test]
+    fn bad_checksum() {
+        let mut link = Loopback::<256>::new();
+        let mut decoder = Decoder::new();
+        let mut bad = frame(&LIGHTING);
+        *bad.last_mut().unwrap() ^= 0x01;
+        link.write(&bad);
+        link.write(&frame(&switches()));
+        assert_eq!(received(&mut link, &mut decoder), [switches()]);
+        assert_eq!(decoder.errors, 1);
+    }
+
+    #[test]
+    fn bad_length() {
+        let mut link = Loopback::<256>::new();
+        let mut decoder = Decoder::new();
+        // longer than any payload
+        link.write(&[START, KIND_LIGHTING, MAX_PAYLOAD as u8 + 1]);
+        link.write(&frame(&LIGHTING));
+        // a good checksum but the wrong length for the kind
+        let mut short = [START, KIND_LIGHTING, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        short[11] = crc8(&short[1..11]);
+        link.write(&short);
+        link.write(&frame(&switches()));
+        assert_eq!(received(&mut link, &mut decoder), [LIGHTING, switches()]);
+        assert_eq!(decoder.errors, 2);
+    }
+
+    #[test]
+    fn start_byte_in_payload() {
+        let mut link = Loopback::<256>::new();
+        let mut decoder = Decoder::new();
+        let mut rows = [START as u32; MATRIX_ROWS];
+        rows[1] = u32::from_le_bytes([START; 4]);
+        let msg = Message::Switches(rows);
+        link.write(&frame(&msg));
+        link.write(&frame(&msg));
+        assert_eq!(received(&mut link, &mut decoder), [msg, msg]);
+        assert_eq!(decoder.errors, 0);
+    }
+
+    #[test]
+    fn resync_finds_every_frame_in_a_dropped_one() {
+        let mut link = Loopback::<256>::new();
+        let mut decoder = Decoder::new();
+        // a frame cut off after its header, its length swallows the two frames behind it
+        link.write(&[START, KIND_SWITCHES, (MATRIX_ROWS * 4) as u8]);
+        link.write(&frame(&LIGHTING));
+        link.write(&frame(&LIGHTING));
+        link.write(&frame(&switches()));
+        assert_eq!(
+            received(&mut link, &mut decoder),
+            [LIGHTING, LIGHTING, switches()]
+        );
+        assert_eq!(decoder.errors, 1);
+    }
+}
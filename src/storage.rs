@@ -0,0 +1,168 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use defmt::info;
+use rp2040_hal::rom_data;
+
+/// Offset(from the start of flash) of the sector reserved for settings, see memory.x
+pub const SETTINGS_OFFSET: u32 = 2048 * 1024 - SECTOR_SIZE;
+/// Offset of the sector for what the board is rather than how it's set up, e.g. the hand
+/// of a split half. Clearing the settings leaves it alone
+#[cfg(feature = "split")]
+pub const IDENTITY_OFFSET: u32 = SETTINGS_OFFSET - SECTOR_SIZE;
+/// Smallest erasable unit of the flash chip
+pub const SECTOR_SIZE: u32 = 4096;
+/// Smallest programmable unit of the flash chip
+pub const PAGE_SIZE: usize = 256;
+/// Where flash is mapped in the address space(XIP)
+const XIP_BASE: u32 = 0x1000_0000;
+/// Size of boot2, the second stage bootloader at the start of flash
+const BOOT2_SIZE: usize = 256;
+
+/// Set by core 0 to ask core 1 to stop running from flash
+static CORE1_PARK: AtomicBool = AtomicBool::new(false);
+/// Set by core 1 once it is spinning in RAM
+static CORE1_PARKED: AtomicBool = AtomicBool::new(false);
+/// Set once core 1 is running its loop, before that there is nothing to park
+static CORE1_RUNNING: AtomicBool = AtomicBool::new(false);
+/// Copy of boot2 in RAM, run after a write to turn the fast XIP mode it set up at boot back
+/// on, the ROM's flash_enter_cmd_xip only goes back to the slow 03h reads
+static mut BOOT2: [u32; BOOT2_SIZE / 4] = [0; BOOT2_SIZE / 4];
+
+/// read from the settings sector
+pub fn read(offset: usize, buf: &mut [u8]) {
+    read_sector(SETTINGS_OFFSET, offset, buf);
+}
+
+/// erase the whole settings sector(everything reads back as 0xFF)
+pub fn erase() {
+    info!("Erasing settings");
+    with_flash(|f| unsafe { flash_erase(f, SETTINGS_OFFSET) });
+}
+
+/// erase the settings sector and write data to the start of it
+/// data is padded with 0xFF to a whole number of pages
+pub fn write(data: &[u8]) {
+    write_sector(SETTINGS_OFFSET, data);
+}
+
+/// read from the identity sector
+#[cfg(feature = "hand-flash")]
+pub fn read_identity(offset: usize, buf: &mut [u8]) {
+    read_sector(IDENTITY_OFFSET, offset, buf);
+}
+
+/// erase the identity sector and write data to the start of it, like write
+#[cfg(feature = "split")]
+pub fn write_identity(data: &[u8]) {
+    write_sector(IDENTITY_OFFSET, data);
+}
+
+fn read_sector(sector: u32, offset: usize, buf: &mut [u8]) {
+    let base = (XIP_BASE + sector) as *const u8;
+    buf.iter_mut().enumerate().for_each(|(i, b)| {
+        *b = unsafe { core::ptr::read_volatile(base.add(offset + i)) };
+    });
+}
+
+fn write_sector(sector: u32, data: &[u8]) {
+    let mut page = [0xFFu8; PAGE_SIZE];
+    with_flash(|f| unsafe {
+        flash_erase(f, sector);
+        for (i, chunk) in data.chunks(PAGE_SIZE).enumerate() {
+            page.fill(0xFF);
+            page[..chunk.len()].copy_from_slice(chunk);
+            flash_program(f, sector + (i * PAGE_SIZE) as u32, &page);
+        }
+    });
+}
+
+/// called by core 1 once it is up and polling core1_poll
+pub fn core1_ready() {
+    CORE1_RUNNING.store(true, Ordering::Release);
+}
+
+/// called by core 1 in its main loop, parks it in RAM while core 0 writes flash
+pub fn core1_poll() {
+    if CORE1_PARK.load(Ordering::Acquire) {
+        cortex_m::interrupt::free(|_| park_core1());
+    }
+}
+
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+fn park_core1() {
+    CORE1_PARKED.store(true, Ordering::Release);
+    while CORE1_PARK.load(Ordering::Acquire) {
+        core::hint::spin_loop();
+    }
+    CORE1_PARKED.store(false, Ordering::Release);
+}
+
+/// ROM function pointers, these have to be looked up before XIP is turned off
+struct FlashFuncs {
+    connect_internal_flash: unsafe extern "C" fn(),
+    flash_exit_xip: unsafe extern "C" fn(),
+    flash_range_erase: unsafe extern "C" fn(u32, usize, u32, u8),
+    flash_range_program: unsafe extern "C" fn(u32, *const u8, usize),
+    flash_flush_cache: unsafe extern "C" fn(),
+    /// the copy of boot2 in BOOT2, like flash_enable_xip_via_boot2 in the pico-sdk
+    enter_xip: unsafe extern "C" fn(),
+}
+
+/// run a flash operation with core 1 parked and interrupts off
+/// Never from inside a critical section: that holds the spinlock core 1 may be waiting on
+/// while this spins until core 1 parks, and it stalls USB for the whole erase
+fn with_flash(op: impl FnOnce(&FlashFuncs)) {
+    // boot2 has to be copied while flash can still be read
+    let boot2 = unsafe { &mut *core::ptr::addr_of_mut!(BOOT2) };
+    for (i, word) in boot2.iter_mut().enumerate() {
+        *word = unsafe { core::ptr::read_volatile((XIP_BASE as *const u32).add(i)) };
+    }
+    let funcs = FlashFuncs {
+        connect_internal_flash: rom_data::connect_internal_flash::ptr(),
+        flash_exit_xip: rom_data::flash_exit_xip::ptr(),
+        flash_range_erase: rom_data::flash_range_erase::ptr(),
+        flash_range_program: rom_data::flash_range_program::ptr(),
+        flash_flush_cache: rom_data::flash_flush_cache::ptr(),
+        // boot2 is position independent thumb code, it returns to its caller when it isn't
+        // entered from the ROM
+        enter_xip: unsafe {
+            core::mem::transmute::<usize, unsafe extern "C" fn()>(boot2.as_ptr() as usize | 1)
+        },
+    };
+    let core1 = CORE1_RUNNING.load(Ordering::Acquire);
+    if core1 {
+        CORE1_PARK.store(true, Ordering::Release);
+        while !CORE1_PARKED.load(Ordering::Acquire) {
+            core::hint::spin_loop();
+        }
+    }
+    cortex_m::interrupt::free(|_| op(&funcs));
+    if core1 {
+        CORE1_PARK.store(false, Ordering::Release);
+        while CORE1_PARKED.load(Ordering::Acquire) {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+unsafe fn flash_erase(f: &FlashFuncs, offset: u32) {
+    (f.connect_internal_flash)();
+    (f.flash_exit_xip)();
+    // 0xD8 is the 64K block erase command, the ROM only uses it if the range allows
+    (f.flash_range_erase)(offset, SECTOR_SIZE as usize, 1 << 16, 0xD8);
+    (f.flash_flush_cache)();
+    (f.enter_xip)();
+}
+
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+unsafe fn flash_program(f: &FlashFuncs, offset: u32, page: &[u8; PAGE_SIZE]) {
+    (f.connect_internal_flash)();
+    (f.flash_exit_xip)();
+    (f.flash_range_program)(offset, page.as_ptr(), PAGE_SIZE);
+    (f.flash_flush_cache)();
+    (f.enter_xip)();
+}
@@ -0,0 +1,101 @@
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use defmt::info;
+use usb_device::prelude::UsbDeviceState;
+
+use crate::{rgb, MATRIX_COLS, MATRIX_ROWS};
+
+/// Time(us) between matrix polls while the bus is suspended, 1ms otherwise
+pub const SUSPENDED_POLL_US: u32 = 5000;
+/// The bus has to be idle at least this long(ms) before a remote wakeup, USB 2.0 7.1.7.7
+const WAKEUP_MIN_IDLE_MS: u32 = 5;
+/// Time(ms) before another wakeup is sent if the host didn't resume
+const WAKEUP_RETRY_MS: u32 = 1000;
+
+/// Set while the host has the USB bus suspended
+static SUSPENDED: AtomicBool = AtomicBool::new(false);
+/// Time(ms) the bus got suspended
+static SUSPENDED_AT: AtomicU32 = AtomicU32::new(0);
+/// Set when the host allows us to wake it(SET_FEATURE DEVICE_REMOTE_WAKEUP)
+static WAKEUP_ALLOWED: AtomicBool = AtomicBool::new(false);
+/// Set when a key press should wake the host, sent from the main loop
+static WAKEUP_PENDING: AtomicBool = AtomicBool::new(false);
+/// Time(ms) the last wakeup was sent
+static LAST_WAKEUP: AtomicU32 = AtomicU32::new(0);
+/// Positions that don't wake the host, bit col of each row
+static NO_WAKEUP: [AtomicU32; MATRIX_ROWS] = [const { AtomicU32::new(0) }; MATRIX_ROWS];
+
+const _: () = assert!(MATRIX_COLS <= 32, "NO_WAKEUP holds a row in a u32");
+
+/// track the USB device state, only called from the USB interrupt
+pub fn set_state(state: UsbDeviceState, wakeup_allowed: bool, now: u32) {
+    WAKEUP_ALLOWED.store(wakeup_allowed, Ordering::Relaxed);
+    let suspended = state == UsbDeviceState::Suspend;
+    if SUSPENDED.load(Ordering::Relaxed) == suspended {
+        return;
+    }
+    if suspended {
+        info!("USB suspended, remote wakeup allowed: {}", wakeup_allowed);
+        SUSPENDED_AT.store(now, Ordering::Relaxed);
+    } else {
+        info!("USB resumed");
+        // waking the bus counts as activity so the LEDs come back
+        rgb::key_activity(now);
+    }
+    SUSPENDED.store(suspended, Ordering::Relaxed);
+}
+
+pub fn is_suspended() -> bool {
+    SUSPENDED.load(Ordering::Relaxed)
+}
+
+/// note a key press at row, col(zero indexed), asks for a wakeup if the bus is suspended
+pub fn key_pressed(row: usize, col: usize) {
+    if is_suspended() && WAKEUP_ALLOWED.load(Ordering::Relaxed) && wakeup_enabled(row, col) {
+        WAKEUP_PENDING.store(true, Ordering::Relaxed);
+    }
+}
+
+/// returns true when the main loop should signal a remote wakeup now
+pub fn take_wakeup(now: u32) -> bool {
+    if !WAKEUP_PENDING.load(Ordering::Relaxed) {
+        return false;
+    }
+    if !is_suspended() {
+        WAKEUP_PENDING.store(false, Ordering::Relaxed);
+        return false;
+    }
+    if now.wrapping_sub(SUSPENDED_AT.load(Ordering::Relaxed)) < WAKEUP_MIN_IDLE_MS
+        || now.wrapping_sub(LAST_WAKEUP.load(Ordering::Relaxed)) < WAKEUP_RETRY_MS
+    {
+        return false;
+    }
+    info!("Sending USB remote wakeup");
+    WAKEUP_PENDING.store(false, Ordering::Relaxed);
+    LAST_WAKEUP.store(now, Ordering::Relaxed);
+    true
+}
+
+pub fn wakeup_enabled(row: usize, col: usize) -> bool {
+    NO_WAKEUP
+        .get(row)
+        .is_some_and(|r| col < MATRIX_COLS && r.load(Ordering::Relaxed) & 1 << col == 0)
+}
+
+/// allow or stop the key at row, col(zero indexed) waking the host
+/// returns false if the position is out of range, only called from the main loop
+pub fn set_wakeup(row: usize, col: usize, enabled: bool) -> bool {
+    let Some(r) = NO_WAKEUP.get(row).filter(|_| col < MATRIX_COLS) else {
+        return false;
+    };
+    let bits = r.load(Ordering::Relaxed);
+    r.store(
+        if enabled {
+            bits & !(1 << col)
+        } else {
+            bits | 1 << col
+        },
+        Ordering::Relaxed,
+    );
+    true
+}
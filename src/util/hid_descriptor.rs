@@ -49,16 +49,16 @@ pub const KEYBOARD_REPORT_DESCRIPTOR: &[u8] = &[
 ];
 
 pub const ZKEY_DESCRIPTOR: &[u8] = &[
-    0x6, 0x0, 0xff,      // USEGE PAGE Vendor
-    0x9, 0x1,             // USAGE Vendor
-    0xa1, 0x1,           // Collection (Application)
-    0x15, 0x0,          // Logical minimum
-    0x26, 0xff, 0x0,   // Logical maximum
-    0x75, 0x8,          // Report size 8
-    0x95, 0x1,          // Report count 1
-    0x09, 0x01,        // USAGE Vendor <---- manually added here
-    0x81, 0x0,          // Input
-    0xc0,                 // End application collection
+    0x6, 0x0, 0xff, // USEGE PAGE Vendor
+    0x9, 0x1, // USAGE Vendor
+    0xa1, 0x1, // Collection (Application)
+    0x15, 0x0, // Logical minimum
+    0x26, 0xff, 0x0, // Logical maximum
+    0x75, 0x8, // Report size 8
+    0x95, 0x1, // Report count 1
+    0x09, 0x01, // USAGE Vendor <---- manually added here
+    0x81, 0x0,  // Input
+    0xc0, // End application collection
 ];
 
 /// NKRO Keyboard - HID Bitmap
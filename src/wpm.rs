@@ -0,0 +1,104 @@
+/// Time(ms) presses are counted over
+const WINDOW_MS: u32 = 10_000;
+/// Time(ms) each bucket covers, presses drop out of the window a bucket at a time
+const BUCKET_MS: u32 = 500;
+const BUCKETS: usize = (WINDOW_MS / BUCKET_MS) as usize;
+/// Presses that make up a word, the usual typing test convention
+const PRESSES_PER_WORD: u32 = 5;
+
+/// Rolling words per minute estimate over the last WINDOW_MS of key presses
+/// Only works on the timestamps it is handed so it doesn't care where they come from
+pub struct Wpm {
+    /// presses in each bucket, indexed by bucket number % BUCKETS
+    buckets: [u16; BUCKETS],
+    /// bucket number(time / BUCKET_MS) of the newest bucket
+    newest: u32,
+}
+
+impl Wpm {
+    pub const fn new() -> Self {
+        Wpm {
+            buckets: [0; BUCKETS],
+            newest: 0,
+        }
+    }
+
+    /// count a key press at time now(ms)
+    pub fn press(&mut self, now: u32) {
+        self.advance(now);
+        let bucket = &mut self.buckets[self.newest as usize % BUCKETS];
+        *bucket = bucket.saturating_add(1);
+    }
+
+    /// words per minute typed over the window ending at now(ms)
+    pub fn wpm(&mut self, now: u32) -> u16 {
+        self.advance(now);
+        let presses: u64 = self.buckets.iter().map(|&b| b as u64).sum();
+        // the buckets cover BUCKETS - 1 whole buckets and however much of the newest has passed
+        let span_ms = (WINDOW_MS - BUCKET_MS + now % BUCKET_MS) as u64;
+        (presses * 60_000 / (PRESSES_PER_WORD as u64 * span_ms)).min(u16::MAX as u64) as u16
+    }
+
+    /// empty the buckets that fell out of the window since the last call
+    fn advance(&mut self, now: u32) {
+        let bucket = now / BUCKET_MS;
+        let passed = bucket.wrapping_sub(self.newest);
+        if passed >= BUCKETS as u32 {
+            self.buckets = [0; BUCKETS];
+        } else {
+            for i in 1..=passed {
+                self.buckets[self.newest.wrapping_add(i) as usize % BUCKETS] = 0;
+            }
+        }
+        self.newest = bucket;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a press every 100ms is 600 presses or 120 words a minute
+    fn typed_for_10s() -> Wpm {
+        let mut wpm = Wpm::new();
+        for t in (0..WINDOW_MS).step_by(100) {
+            wpm.press(t);
+        }
+        wpm
+    }
+
+    #[test]
+    fn steady_typing() {
+        let mut wpm = typed_for_10s();
+        assert_eq!(wpm.wpm(9_999), 120);
+        // the first bucket rolled out along with its half second
+        assert_eq!(wpm.wpm(10_000), 120);
+    }
+
+    #[test]
+    fn bucket_rolls_over() {
+        let mut wpm = Wpm::new();
+        for _ in 0..10 {
+            wpm.press(0);
+        }
+        assert_eq!(wpm.wpm(9_999), 12);
+        // bucket 0 is reused for 10_000-10_499, its old presses are gone
+        assert_eq!(wpm.wpm(10_000), 0);
+        wpm.press(10_000);
+        assert_eq!(wpm.wpm(10_000), 1);
+    }
+
+    #[test]
+    fn decays_to_zero_when_idle() {
+        let mut wpm = typed_for_10s();
+        let mut last = u16::MAX;
+        for t in (10_000..20_000).step_by(BUCKET_MS as usize) {
+            let now = wpm.wpm(t);
+            assert!(now <= last, "went up to {} at {}", now, t);
+            last = now;
+        }
+        assert_eq!(wpm.wpm(20_000), 0);
+        // much later still nothing, no matter how many buckets were skipped
+        assert_eq!(wpm.wpm(1_000_000), 0);
+    }
+}